@@ -0,0 +1,156 @@
+//! Types shared by the factory and the locked token.
+
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+use near_sdk::{AccountId, Balance, Timestamp};
+
+mod dec_format;
+mod oracle_feed;
+mod price;
+mod validation;
+
+pub use crate::dec_format::*;
+pub use crate::oracle_feed::*;
+pub use crate::price::*;
+pub use crate::validation::*;
+
+pub type AssetId = String;
+pub type DurationSec = u32;
+pub type TokenId = String;
+
+/// Arguments of the locked token `new` method, created by the factory.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenArgs {
+    pub locked_token_account_id: AccountId,
+    pub token_id: TokenId,
+    pub meta: FungibleTokenMetadata,
+    pub owner_id: AccountId,
+    /// Accounts which can unlock the token without the price condition
+    pub guardian_ids: Vec<AccountId>,
+    /// Number of guardian approvals required to execute a guardian action
+    pub guardian_threshold: u32,
+    /// Guardian approvals expire after this period
+    pub guardian_approval_period_sec: DurationSec,
+    /// Delay between guardians approving the backup unlock and the token unlocking
+    pub emergency_unlock_delay_sec: DurationSec,
+    pub price_oracle_account_ids: Vec<AccountId>,
+    /// Number of fresh oracle reports required to make an unlock decision
+    pub oracle_quorum: u32,
+    pub oracle_feed: OracleFeed,
+    pub asset_id: AssetId,
+    pub minimum_unlock_price: Price,
+    pub unlock_direction: UnlockDirection,
+    /// Timestamp after which anyone can unlock the token without the price condition
+    #[serde(with = "option_u64_dec_format")]
+    pub unlock_deadline: Option<Timestamp>,
+    pub unlocking_duration_sec: DurationSec,
+    pub min_confirmations: u32,
+    pub max_confirmation_gap_sec: DurationSec,
+    pub max_price_age_sec: DurationSec,
+    /// Penalty of `early_unwrap` in basis points, 0 disables early unwrap
+    pub early_unwrap_penalty_bps: u32,
+    /// Receives penalties of `early_unwrap`. If not set, penalties are redistributed to holders
+    pub penalty_beneficiary_id: Option<AccountId>,
+    /// Deposits smaller than this don't get the storage of new accounts paid from the pool
+    #[serde(with = "u128_dec_format")]
+    pub min_sponsored_deposit: Balance,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct TokenInitArgs<'a> {
+    args: &'a TokenArgs,
+}
+
+impl TokenArgs {
+    /// JSON arguments of the function call to the token `new` method.
+    pub fn to_init_args(&self) -> Vec<u8> {
+        serde_json::to_vec(&TokenInitArgs { args: self }).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::serde_json::Value;
+
+    #[derive(Deserialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct InitArgs {
+        args: TokenArgs,
+    }
+
+    fn token_args() -> TokenArgs {
+        TokenArgs {
+            locked_token_account_id: "wrap.near".to_string(),
+            token_id: "near-at-10-0".to_string(),
+            meta: FungibleTokenMetadata {
+                spec: "ft-1.0.0".to_string(),
+                name: "NEAR at $10".to_string(),
+                symbol: "NEAR@10".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 24,
+            },
+            owner_id: "owner.near".to_string(),
+            guardian_ids: vec!["guardian.near".to_string()],
+            guardian_threshold: 1,
+            guardian_approval_period_sec: 3600,
+            emergency_unlock_delay_sec: 7200,
+            price_oracle_account_ids: vec!["priceoracle.near".to_string()],
+            oracle_quorum: 1,
+            oracle_feed: OracleFeed::Pyth {
+                price_id: "c415de8d2eba7db216527dff4b60e8f3a5311c740dadb233e13e12547e226750"
+                    .to_string(),
+                max_confidence_bps: 100,
+            },
+            asset_id: "wrap.near".to_string(),
+            minimum_unlock_price: Price {
+                multiplier: 100000,
+                decimals: 28,
+            },
+            unlock_direction: UnlockDirection::Below,
+            unlock_deadline: Some(u64::MAX),
+            unlocking_duration_sec: 86400,
+            min_confirmations: 3,
+            max_confirmation_gap_sec: 600,
+            max_price_age_sec: 300,
+            early_unwrap_penalty_bps: 500,
+            penalty_beneficiary_id: None,
+            min_sponsored_deposit: 10u128.pow(24),
+        }
+    }
+
+    #[test]
+    pub fn test_init_args_round_trip() {
+        let init_args = token_args().to_init_args();
+        let parsed: InitArgs = serde_json::from_slice(&init_args).unwrap();
+        assert_eq!(parsed.args.to_init_args(), init_args);
+    }
+
+    #[test]
+    pub fn test_init_args_format() {
+        let value: Value = serde_json::from_slice(&token_args().to_init_args()).unwrap();
+        let args = &value["args"];
+        assert_eq!(args["unlock_deadline"], "18446744073709551615");
+        assert_eq!(args["minimum_unlock_price"]["multiplier"], "100000");
+        assert_eq!(args["minimum_unlock_price"]["decimals"], 28);
+        assert_eq!(args["unlock_direction"], "Below");
+        assert_eq!(args["penalty_beneficiary_id"], Value::Null);
+        assert_eq!(args["oracle_feed"]["Pyth"]["max_confidence_bps"], 100);
+        assert_eq!(args["min_sponsored_deposit"], "1000000000000000000000000");
+        assert_eq!(args.as_object().unwrap().len(), 22);
+    }
+
+    #[test]
+    pub fn test_borsh_round_trip() {
+        let args = token_args();
+        let bytes = args.try_to_vec().unwrap();
+        let parsed = TokenArgs::try_from_slice(&bytes).unwrap();
+        assert_eq!(parsed.to_init_args(), args.to_init_args());
+    }
+}
@@ -0,0 +1,25 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Format of the price reports which the token accepts from its oracles.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OracleFeed {
+    /// `PriceData` pushed by the NEAR price oracle through `oracle_on_call`.
+    NearPriceOracle,
+    /// Pyth-style prices with a confidence interval, relayed through `on_pyth_price_update`.
+    Pyth {
+        /// Identifier of the price feed of the asset
+        price_id: String,
+        /// Reports with a confidence interval wider than this share of the price are ignored
+        max_confidence_bps: u32,
+    },
+}
+
+// Not derived, see `UnlockDirection`
+#[allow(clippy::derivable_impls)]
+impl Default for OracleFeed {
+    fn default() -> Self {
+        OracleFeed::NearPriceOracle
+    }
+}
@@ -0,0 +1,243 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Balance;
+use std::cmp::Ordering;
+
+use crate::u128_dec_format;
+
+const MAX_U128_DECIMALS: u8 = 38;
+
+mod u256 {
+    #![allow(clippy::all)]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+pub use u256::U256;
+
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum UnlockDirection {
+    /// Unlocks once the price rises to the target or above it.
+    Above,
+    /// Unlocks once the price drops to the target or below it.
+    Below,
+}
+
+// `#[default]` variants need a newer compiler than the pinned toolchain of `build_docker.sh`
+#[allow(clippy::derivable_impls)]
+impl Default for UnlockDirection {
+    fn default() -> Self {
+        UnlockDirection::Above
+    }
+}
+
+impl UnlockDirection {
+    pub fn is_reached(&self, price: &Price, target_price: &Price) -> bool {
+        match self {
+            UnlockDirection::Above => price >= target_price,
+            UnlockDirection::Below => price <= target_price,
+        }
+    }
+
+    pub fn as_operator(&self) -> &'static str {
+        match self {
+            UnlockDirection::Above => ">=",
+            UnlockDirection::Below => "<=",
+        }
+    }
+
+    /// Word used in the child token account ID and name: `near-at-5-0`, `NEAR below $5`
+    pub fn token_id_separator(&self) -> &'static str {
+        match self {
+            UnlockDirection::Above => "at",
+            UnlockDirection::Below => "below",
+        }
+    }
+
+    /// Separator used in the child token symbol: `NEAR@5`, `NEAR<5`
+    pub fn symbol_separator(&self) -> &'static str {
+        match self {
+            UnlockDirection::Above => "@",
+            UnlockDirection::Below => "<",
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Price {
+    #[serde(with = "u128_dec_format")]
+    pub multiplier: Balance,
+    pub decimals: u8,
+}
+
+impl PartialEq<Self> for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares `multiplier / 10^decimals` exactly.
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.decimals < other.decimals {
+            return other.cmp(self).reverse();
+        }
+
+        let decimals_diff = self.decimals - other.decimals;
+
+        if other.multiplier == 0 || self.multiplier == 0 {
+            return self.multiplier.cmp(&other.multiplier);
+        }
+
+        // `other.multiplier * 10^decimals_diff >= 10^39 > self.multiplier`
+        if decimals_diff > MAX_U128_DECIMALS {
+            return Ordering::Less;
+        }
+
+        // Fits into 256 bits, since both factors are below 2^128
+        let om = U256::from(other.multiplier) * U256::from(10u128.pow(decimals_diff as u32));
+        U256::from(self.multiplier).cmp(&om)
+    }
+}
+
+impl Price {
+    /// Formats the price as a decimal number, where `base_decimals` of the price decimals are
+    /// the decimals of the token. E.g. the price `50000` with `28` decimals of a token with `24`
+    /// decimals is `"5"`.
+    pub fn to_decimal_string(&self, base_decimals: u8) -> String {
+        if self.decimals < base_decimals {
+            let zeros = "0".repeat((base_decimals - self.decimals) as usize);
+            return format!("{}{}", self.multiplier, zeros);
+        }
+        let decimals = (self.decimals - base_decimals) as usize;
+        let digits = format!("{:0>width$}", self.multiplier, width = decimals + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            integer.to_string()
+        } else {
+            format!("{}.{}", integer, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    fn p(multiplier: u128, decimals: u8) -> Price {
+        Price {
+            multiplier,
+            decimals,
+        }
+    }
+
+    #[test]
+    pub fn test_price_cmp() {
+        assert!(p(10, 0) < p(11, 0));
+        assert!(p(11, 0) > p(10, 0));
+        assert!(p(11, 0) == p(11, 0));
+
+        assert!(p(10, 10) < p(11, 10));
+        assert!(p(11, 10) > p(10, 10));
+        assert!(p(11, 10) == p(11, 10));
+
+        assert!(p(100, 10) == p(10, 9));
+        assert!(p(10, 9) == p(100, 10));
+
+        assert!(p(101, 10) > p(10, 9));
+        assert!(p(10, 9) < p(101, 10));
+        assert!(p(99, 10) < p(10, 9));
+        assert!(p(10, 9) > p(99, 10));
+
+        assert!(p(101, 40) < p(10, 0));
+        assert!(p(10, 0) > p(101, 40));
+
+        assert!(p(u128::MAX, 0) > p(1, 0));
+        assert!(p(u128::MAX, 38) > p(3, 0));
+        assert!(p(u128::MAX, 38) < p(4, 0));
+        assert!(p(1, 255) > p(0, 0));
+        assert!(p(0, 255) == p(0, 0));
+        assert!(p(u128::MAX, 77) < p(1, 38));
+    }
+
+    fn big_cmp(a: &Price, b: &Price) -> Ordering {
+        let decimals = std::cmp::max(a.decimals, b.decimals);
+        let scale = |price: &Price| {
+            BigUint::from(price.multiplier)
+                * BigUint::from(10u32).pow((decimals - price.decimals) as u32)
+        };
+        scale(a).cmp(&scale(b))
+    }
+
+    fn any_price() -> impl Strategy<Value = Price> {
+        (any::<u128>(), any::<u8>()).prop_map(|(multiplier, decimals)| p(multiplier, decimals))
+    }
+
+    /// Prices with close decimals, where the multipliers decide the order.
+    fn close_prices() -> impl Strategy<Value = (Price, Price)> {
+        (any::<u128>(), 0u8..=60, any::<u128>(), 0u8..=60)
+            .prop_map(|(a, ad, b, bd)| (p(a, ad), p(b, bd)))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_price_cmp_is_exact(a in any_price(), b in any_price()) {
+            prop_assert_eq!(a.cmp(&b), big_cmp(&a, &b));
+        }
+
+        #[test]
+        fn prop_price_cmp_is_exact_for_close_decimals((a, b) in close_prices()) {
+            prop_assert_eq!(a.cmp(&b), big_cmp(&a, &b));
+        }
+
+        #[test]
+        fn prop_price_ord_is_consistent(a in any_price(), b in any_price(), c in any_price()) {
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+            prop_assert_eq!(a.partial_cmp(&b), Some(a.cmp(&b)));
+            if a <= b && b <= c {
+                prop_assert!(a <= c);
+            }
+        }
+
+        #[test]
+        fn prop_price_scaling_keeps_value(multiplier in 0u128..u128::MAX / 10, decimals in 0u8..u8::MAX) {
+            prop_assert_eq!(p(multiplier, decimals), p(multiplier * 10, decimals + 1));
+        }
+    }
+
+    #[test]
+    pub fn test_unlock_direction() {
+        assert!(UnlockDirection::Above.is_reached(&p(11, 0), &p(10, 0)));
+        assert!(UnlockDirection::Above.is_reached(&p(100, 1), &p(10, 0)));
+        assert!(!UnlockDirection::Above.is_reached(&p(9, 0), &p(10, 0)));
+
+        assert!(UnlockDirection::Below.is_reached(&p(9, 0), &p(10, 0)));
+        assert!(UnlockDirection::Below.is_reached(&p(100, 1), &p(10, 0)));
+        assert!(!UnlockDirection::Below.is_reached(&p(11, 0), &p(10, 0)));
+    }
+
+    #[test]
+    pub fn test_price_to_decimal_string() {
+        assert_eq!(p(50000, 28).to_decimal_string(24), "5");
+        assert_eq!(p(12345, 28).to_decimal_string(24), "1.2345");
+        assert_eq!(p(5, 28).to_decimal_string(24), "0.0005");
+        assert_eq!(p(0, 28).to_decimal_string(24), "0");
+        assert_eq!(p(5, 20).to_decimal_string(24), "50000");
+    }
+}
@@ -0,0 +1,121 @@
+//! Checks of the token configuration, shared by the factory and the locked token.
+
+use crate::OracleFeed;
+use near_sdk::AccountId;
+
+pub const MAX_BPS: u32 = 10_000;
+/// Early unwrap always pays out part of the underlying tokens
+pub const MAX_EARLY_UNWRAP_PENALTY_BPS: u32 = 5_000;
+
+pub fn assert_valid_oracles(price_oracle_account_ids: &[AccountId], oracle_quorum: u32) {
+    assert!(
+        oracle_quorum > 0 && oracle_quorum as usize <= price_oracle_account_ids.len(),
+        "Oracle quorum must be between 1 and the number of price oracles"
+    );
+    for (index, oracle_id) in price_oracle_account_ids.iter().enumerate() {
+        assert!(
+            !price_oracle_account_ids[..index].contains(oracle_id),
+            "Duplicate price oracle {}",
+            oracle_id
+        );
+    }
+}
+
+pub fn assert_valid_oracle_feed(oracle_feed: &OracleFeed) {
+    if let OracleFeed::Pyth {
+        price_id,
+        max_confidence_bps,
+    } = oracle_feed
+    {
+        assert!(!price_id.is_empty(), "Missing price feed ID");
+        assert!(
+            *max_confidence_bps > 0 && *max_confidence_bps <= MAX_BPS,
+            "Maximum confidence must be between 1 and {} basis points",
+            MAX_BPS
+        );
+    }
+}
+
+/// An empty guardian set disables the backup unlock.
+pub fn assert_valid_guardians(guardian_ids: &[AccountId], guardian_threshold: u32) {
+    if guardian_ids.is_empty() {
+        assert_eq!(
+            guardian_threshold, 0,
+            "Guardian threshold requires guardians"
+        );
+        return;
+    }
+    assert!(
+        guardian_threshold > 0 && guardian_threshold as usize <= guardian_ids.len(),
+        "Guardian threshold must be between 1 and the number of guardians"
+    );
+    for (index, guardian_id) in guardian_ids.iter().enumerate() {
+        assert!(
+            !guardian_ids[..index].contains(guardian_id),
+            "Duplicate guardian {}",
+            guardian_id
+        );
+    }
+}
+
+pub fn assert_valid_early_unwrap_penalty(early_unwrap_penalty_bps: u32) {
+    assert!(
+        early_unwrap_penalty_bps <= MAX_EARLY_UNWRAP_PENALTY_BPS,
+        "Early unwrap penalty can't exceed {} basis points",
+        MAX_EARLY_UNWRAP_PENALTY_BPS
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_ids(ids: &[&str]) -> Vec<AccountId> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    pub fn test_valid_config() {
+        assert_valid_oracles(&account_ids(&["a.near", "b.near"]), 2);
+        assert_valid_guardians(&account_ids(&["a.near", "b.near"]), 1);
+        assert_valid_guardians(&[], 0);
+        assert_valid_oracle_feed(&OracleFeed::Pyth {
+            price_id: "c415de8d".to_string(),
+            max_confidence_bps: MAX_BPS,
+        });
+        assert_valid_early_unwrap_penalty(MAX_EARLY_UNWRAP_PENALTY_BPS);
+    }
+
+    #[test]
+    #[should_panic(expected = "Oracle quorum must be between 1 and the number of price oracles")]
+    pub fn test_oracle_quorum_too_high() {
+        assert_valid_oracles(&account_ids(&["a.near"]), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate price oracle a.near")]
+    pub fn test_duplicate_oracle() {
+        assert_valid_oracles(&account_ids(&["a.near", "a.near"]), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate guardian a.near")]
+    pub fn test_duplicate_guardian() {
+        assert_valid_guardians(&account_ids(&["a.near", "a.near"]), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Guardian threshold requires guardians")]
+    pub fn test_threshold_without_guardians() {
+        assert_valid_guardians(&[], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum confidence must be between 1 and 10000 basis points")]
+    pub fn test_zero_confidence() {
+        assert_valid_oracle_feed(&OracleFeed::Pyth {
+            price_id: "c415de8d".to_string(),
+            max_confidence_bps: 0,
+        });
+    }
+}
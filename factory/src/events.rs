@@ -0,0 +1,55 @@
+use crate::*;
+
+const EVENT_STANDARD: &str = "locked_ft_factory";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// NEP-297 events emitted by the factory.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    WhitelistToken {
+        token_id: TokenAccountId,
+        asset_id: AssetId,
+        ticker: Option<String>,
+    },
+    UpdateWhitelistedToken { token_id: TokenAccountId },
+    WhitelistPriceOracle { account_id: AccountId },
+    WhitelistGuardian { account_id: AccountId },
+    OwnerProposed {
+        owner_id: AccountId,
+        proposed_owner_id: Option<AccountId>,
+    },
+    OwnerChange {
+        old_owner_id: AccountId,
+        new_owner_id: AccountId,
+    },
+    CreateToken {
+        token_account_id: TokenAccountId,
+        locked_token_account_id: TokenAccountId,
+        asset_id: AssetId,
+        minimum_unlock_price: Price,
+        unlock_direction: UnlockDirection,
+        creator_id: AccountId,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl Event {
+    pub fn emit(&self) {
+        let event_log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+        log!("EVENT_JSON:{}", serde_json::to_string(&event_log).unwrap());
+    }
+}
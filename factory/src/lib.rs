@@ -1,15 +1,21 @@
 use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
 use near_sdk::{
-    AccountId, Balance, BorshStorageKey, env, ext_contract, Gas, log, near_bindgen, PanicOnDefault, Promise,
+    AccountId, assert_one_yocto, Balance, BorshStorageKey, env, ext_contract, Gas, log, near_bindgen, PanicOnDefault, Promise,
+    Timestamp,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::env::STORAGE_PRICE_PER_BYTE;
-use near_sdk::json_types::{U128, ValidAccountId};
+use near_sdk::json_types::{U128, U64, ValidAccountId};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json;
 
+use crate::events::*;
+use locked_ft_common::*;
+
+mod events;
 mod migrate;
+mod owner;
 
 near_sdk::setup_alloc!();
 
@@ -20,10 +26,25 @@ const GAS: Gas = 50_000_000_000_000;
 const GAS_FT_METADATA_READ: Gas = 25_000_000_000_000;
 const GAS_FT_METADATA_WRITE: Gas = 25_000_000_000_000;
 const NO_DEPOSIT: Balance = 0;
-const BACKUP_TRIGGER_ACCOUNT_ID: &str = "dreamproject.near";
 
-type TokenId = String;
-pub type AssetId = String;
+const DEFAULT_UNLOCKING_DURATION_SEC: DurationSec = 24 * 60 * 60;
+const MIN_UNLOCKING_DURATION_SEC: DurationSec = 60 * 60;
+const MAX_UNLOCKING_DURATION_SEC: DurationSec = 30 * 24 * 60 * 60;
+const DEFAULT_MIN_CONFIRMATIONS: u32 = 6;
+const MAX_MIN_CONFIRMATIONS: u32 = 100;
+const MIN_CONFIRMATION_GAP_SEC: DurationSec = 60;
+// By default confirming reports can't be further apart than a twelfth of the unlocking window.
+const DEFAULT_CONFIRMATION_GAPS_PER_WINDOW: DurationSec = 12;
+const DEFAULT_MAX_PRICE_AGE_SEC: DurationSec = 5 * 60;
+const MIN_MAX_PRICE_AGE_SEC: DurationSec = 60;
+const MAX_MAX_PRICE_AGE_SEC: DurationSec = 60 * 60;
+const DEFAULT_GUARDIAN_APPROVAL_PERIOD_SEC: DurationSec = 7 * 24 * 60 * 60;
+const MIN_GUARDIAN_APPROVAL_PERIOD_SEC: DurationSec = 60 * 60;
+const MAX_GUARDIAN_APPROVAL_PERIOD_SEC: DurationSec = 30 * 24 * 60 * 60;
+const DEFAULT_EMERGENCY_UNLOCK_DELAY_SEC: DurationSec = 2 * 24 * 60 * 60;
+const MIN_EMERGENCY_UNLOCK_DELAY_SEC: DurationSec = 60 * 60;
+const MAX_EMERGENCY_UNLOCK_DELAY_SEC: DurationSec = 30 * 24 * 60 * 60;
+
 pub type TokenAccountId = AccountId;
 
 #[ext_contract(ext_ft)]
@@ -49,7 +70,8 @@ enum StorageKey {
     StorageDeposits,
     WhitelistedTokens,
     WhitelistedTokensV1,
-    WhitelistedPriceOracles
+    WhitelistedPriceOracles,
+    WhitelistedGuardians
 }
 
 #[near_bindgen]
@@ -60,6 +82,11 @@ pub struct TokenFactory {
     pub storage_balance_cost: Balance,
     pub whitelisted_tokens: UnorderedMap<AccountId, WhitelistedToken>,
     pub whitelisted_price_oracles: UnorderedSet<AccountId>,
+    /// Accounts which can be guardians of new tokens, besides the backup trigger
+    pub whitelisted_guardians: UnorderedSet<AccountId>,
+    pub owner_id: AccountId,
+    pub proposed_owner_id: Option<AccountId>,
+    pub backup_trigger_account_id: Option<AccountId>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault, Serialize)]
@@ -72,32 +99,47 @@ pub struct WhitelistedToken {
     pub metadata: FungibleTokenMetadata,
 }
 
+/// Lock terms which differ from the defaults add a terms ID to the token ID, name and symbol,
+/// e.g. `near-at-5-0-1a2b3c4d`, so only tokens with the default terms get the canonical names.
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct TokenArgsInput {
     token_id: ValidAccountId,
     target_price: U128,
-    price_oracle_account_id: Option<ValidAccountId>,
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
-#[serde(crate = "near_sdk::serde")]
-pub struct Price {
-    #[serde(with = "u128_dec_format")]
-    multiplier: Balance,
-    decimals: u8,
-}
-
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault, Serialize)]
-#[serde(crate = "near_sdk::serde")]
-pub struct TokenArgs {
-    pub locked_token_account_id: TokenAccountId,
-    pub token_id: TokenId,
-    pub meta: FungibleTokenMetadata,
-    pub backup_trigger_account_id: Option<AccountId>,
-    pub price_oracle_account_id: AccountId,
-    pub asset_id: AssetId,
-    pub minimum_unlock_price: Price,
+    #[serde(default)]
+    price_oracle_account_ids: Vec<ValidAccountId>,
+    // Number of fresh oracle reports required to make an unlock decision. Defaults to a majority.
+    oracle_quorum: Option<u32>,
+    // Format of the price oracle reports. Defaults to the NEAR price oracle.
+    oracle_feed: Option<OracleFeed>,
+    unlock_direction: Option<UnlockDirection>,
+    // Timestamp in nanoseconds after which anyone can unlock the token without the price condition.
+    unlock_deadline: Option<U64>,
+    // How long the price condition has to hold before the token unlocks. Defaults to 24 hours.
+    unlocking_duration_sec: Option<DurationSec>,
+    // Number of oracle reports confirming the price during the unlocking window. Defaults to 6.
+    min_confirmations: Option<u32>,
+    // Maximum time between two confirming reports. Defaults to a twelfth of the unlocking duration.
+    max_confirmation_gap_sec: Option<DurationSec>,
+    // Oracle reports older than this are rejected. Defaults to 5 minutes.
+    max_price_age_sec: Option<DurationSec>,
+    // Penalty in basis points for unwrapping before unlock. Early unwrap is disabled by default.
+    early_unwrap_penalty_bps: Option<u32>,
+    // Receives early unwrap penalties. If missing, penalties are redistributed to the holders.
+    penalty_beneficiary_id: Option<ValidAccountId>,
+    // Accounts which can unlock the token without the price condition. Defaults to the factory backup trigger.
+    // Other guardians have to be whitelisted by the factory owner.
+    guardian_ids: Option<Vec<ValidAccountId>>,
+    // Number of guardian approvals required to unlock or rotate guardians. Defaults to a majority.
+    guardian_threshold: Option<u32>,
+    // Guardian approvals expire after this period. Defaults to 7 days.
+    guardian_approval_period_sec: Option<DurationSec>,
+    // Delay before the unlock approved by guardians can be finalized. Defaults to 48 hours.
+    emergency_unlock_delay_sec: Option<DurationSec>,
+    // Attached to the token to pay the storage of depositors which are not registered yet.
+    storage_pool: Option<U128>,
+    // Deposits which get the storage of new accounts paid from the pool. Defaults to 1 whole underlying token.
+    min_sponsored_deposit: Option<U128>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault, Serialize)]
@@ -131,10 +173,26 @@ pub struct TokenArgsOutput {
     pub token_id: Option<TokenAccountId>,
     pub locked_token_account_id: TokenAccountId,
     pub meta: FungibleTokenMetadata,
-    pub backup_trigger_account_id: Option<AccountId>,
-    pub price_oracle_account_id: AccountId,
+    pub owner_id: AccountId,
+    pub guardian_ids: Vec<AccountId>,
+    pub guardian_threshold: u32,
+    pub guardian_approval_period_sec: DurationSec,
+    pub emergency_unlock_delay_sec: DurationSec,
+    pub price_oracle_account_ids: Vec<AccountId>,
+    pub oracle_quorum: u32,
+    pub oracle_feed: OracleFeed,
     pub asset_id: AssetId,
     pub minimum_unlock_price: Price,
+    pub unlock_direction: UnlockDirection,
+    #[serde(with = "option_u64_dec_format")]
+    pub unlock_deadline: Option<Timestamp>,
+    pub unlocking_duration_sec: DurationSec,
+    pub min_confirmations: u32,
+    pub max_confirmation_gap_sec: DurationSec,
+    pub max_price_age_sec: DurationSec,
+    pub early_unwrap_penalty_bps: u32,
+    pub penalty_beneficiary_id: Option<AccountId>,
+    pub min_sponsored_deposit: U128,
 }
 
 impl TokenArgsOutput {
@@ -144,10 +202,25 @@ impl TokenArgsOutput {
                 token_id,
                 locked_token_account_id: token.locked_token_account_id,
                 meta: token.meta,
-                backup_trigger_account_id: token.backup_trigger_account_id,
-                price_oracle_account_id: token.price_oracle_account_id,
+                owner_id: token.owner_id,
+                guardian_ids: token.guardian_ids,
+                guardian_threshold: token.guardian_threshold,
+                guardian_approval_period_sec: token.guardian_approval_period_sec,
+                emergency_unlock_delay_sec: token.emergency_unlock_delay_sec,
+                price_oracle_account_ids: token.price_oracle_account_ids,
+                oracle_quorum: token.oracle_quorum,
+                oracle_feed: token.oracle_feed,
                 asset_id: token.asset_id,
                 minimum_unlock_price: token.minimum_unlock_price,
+                unlock_direction: token.unlock_direction,
+                unlock_deadline: token.unlock_deadline,
+                unlocking_duration_sec: token.unlocking_duration_sec,
+                min_confirmations: token.min_confirmations,
+                max_confirmation_gap_sec: token.max_confirmation_gap_sec,
+                max_price_age_sec: token.max_price_age_sec,
+                early_unwrap_penalty_bps: token.early_unwrap_penalty_bps,
+                penalty_beneficiary_id: token.penalty_beneficiary_id,
+                min_sponsored_deposit: U128(token.min_sponsored_deposit),
             })
         } else {
             None
@@ -158,7 +231,7 @@ impl TokenArgsOutput {
 #[near_bindgen]
 impl TokenFactory {
     #[init]
-    pub fn new() -> Self {
+    pub fn new(owner_id: ValidAccountId, backup_trigger_account_id: Option<ValidAccountId>) -> Self {
         let mut storage_deposits = LookupMap::new(StorageKey::StorageDeposits);
 
         let initial_storage_usage = env::storage_usage();
@@ -173,7 +246,11 @@ impl TokenFactory {
             storage_deposits,
             storage_balance_cost,
             whitelisted_tokens: UnorderedMap::new(StorageKey::WhitelistedTokens),
-            whitelisted_price_oracles: UnorderedSet::new(StorageKey::WhitelistedPriceOracles)
+            whitelisted_price_oracles: UnorderedSet::new(StorageKey::WhitelistedPriceOracles),
+            whitelisted_guardians: UnorderedSet::new(StorageKey::WhitelistedGuardians),
+            owner_id: owner_id.into(),
+            proposed_owner_id: None,
+            backup_trigger_account_id: backup_trigger_account_id.map(|a| a.into()),
         }
     }
 
@@ -187,13 +264,13 @@ impl TokenFactory {
         self.internal_whitelist_token(&token_id, asset_id, ticker, ft_metadata);
     }
 
-    #[private]
     pub fn whitelist_token(
         &mut self,
         token_id: ValidAccountId,
         asset_id: ValidAccountId,
         ticker: Option<String>,
     ) -> Promise {
+            self.assert_owner();
             ext_ft::ft_metadata(
                 &token_id,
                 NO_DEPOSIT,
@@ -208,18 +285,26 @@ impl TokenFactory {
             ))
     }
 
-    #[private]
     pub fn whitelist_token_with_metadata(&mut self, token_id: ValidAccountId,
                                          asset_id: ValidAccountId,
                                          ticker: Option<String>,
                                          metadata: FungibleTokenMetadata) {
+        self.assert_owner();
         self.internal_whitelist_token(&(token_id.into()), asset_id.into(), ticker, metadata);
     }
 
-    #[private]
     pub fn whitelist_price_oracle(&mut self, account_id: ValidAccountId) {
+        self.assert_owner();
         let account: AccountId = account_id.into();
         self.whitelisted_price_oracles.insert(&account);
+        Event::WhitelistPriceOracle { account_id: account }.emit();
+    }
+
+    pub fn whitelist_guardian(&mut self, account_id: ValidAccountId) {
+        self.assert_owner();
+        let account: AccountId = account_id.into();
+        self.whitelisted_guardians.insert(&account);
+        Event::WhitelistGuardian { account_id: account }.emit();
     }
 
     #[payable]
@@ -242,6 +327,11 @@ impl TokenFactory {
         self.tokens.len()
     }
 
+    pub fn get_whitelisted_guardians(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        let guardian_ids = self.whitelisted_guardians.as_vector();
+        (from_index..std::cmp::min(from_index + limit, guardian_ids.len())).filter_map(|index| guardian_ids.get(index)).collect()
+    }
+
     pub fn get_whitelisted_price_oracles(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
         let contract_ids = self.whitelisted_price_oracles.as_vector();
         (from_index..std::cmp::min(from_index + limit, contract_ids.len())).filter_map(|contract_id| contract_ids.get(contract_id)).collect()
@@ -286,7 +376,7 @@ impl TokenFactory {
 
     pub fn get_token_name(&self, token_args: TokenArgsInput) -> AccountId {
         let whitelisted_token = self.internal_get_whitelisted_token(&(token_args.token_id.clone().into()));
-        let (token_account_id, _, _, _) = generate_token_namespace(&token_args, &whitelisted_token);
+        let (token_account_id, _, _, _, _, _) = generate_token_namespace(&token_args, &whitelisted_token);
         token_account_id
     }
 
@@ -297,6 +387,11 @@ impl TokenFactory {
                                 metadata: FungibleTokenMetadata) {
         assert!(is_valid_symbol(&metadata.symbol.to_ascii_lowercase()), "Invalid Token symbol");
 
+        Event::WhitelistToken {
+            token_id: token_id.clone(),
+            asset_id: asset_id.clone(),
+            ticker: ticker.clone(),
+        }.emit();
         self.whitelisted_tokens.insert(token_id, &WhitelistedToken { asset_id, ticker, metadata });
     }
 
@@ -308,15 +403,16 @@ impl TokenFactory {
         self.tokens.get(token_id).expect("Token wasn't created")
     }
 
-    #[private]
     pub fn update_whitelisted_token_metadata(&mut self, token_id: TokenAccountId, metadata: FungibleTokenMetadata) {
+        self.assert_owner();
         let mut token = self.internal_get_whitelisted_token(&token_id);
         token.metadata = metadata;
         self.whitelisted_tokens.insert(&token_id, &token);
+        Event::UpdateWhitelistedToken { token_id }.emit();
     }
 
-    #[private]
     pub fn update_token_metadata(&mut self, token_id: TokenAccountId, meta: FungibleTokenMetadata) {
+        self.assert_owner();
         let mut token = self.internal_get_token(&token_id);
         token.meta = meta;
         self.tokens.insert(&token_id, &token);
@@ -329,16 +425,103 @@ impl TokenFactory {
         }
 
         let whitelisted_token = self.internal_get_whitelisted_token(&(token_args.token_id.clone().into()));
-        let (token_account_id, ticker, price, token_id) = generate_token_namespace(&token_args, &whitelisted_token);
+        let (token_account_id, ticker, price, token_id, unlock_direction, terms_id) = generate_token_namespace(&token_args, &whitelisted_token);
 
-        let input_price_oracle_account_id: AccountId = token_args.price_oracle_account_id.expect("Price Oracle Contract is missing").into();
-        assert!(self.whitelisted_price_oracles.contains(&input_price_oracle_account_id), "Price Oracle wasn't whitelisted");
+        let price_oracle_account_ids: Vec<AccountId> = token_args.price_oracle_account_ids.into_iter().map(|a| a.into()).collect();
+        assert!(!price_oracle_account_ids.is_empty(), "Price Oracle Contract is missing");
+        for price_oracle_account_id in &price_oracle_account_ids {
+            assert!(self.whitelisted_price_oracles.contains(price_oracle_account_id), "Price Oracle wasn't whitelisted");
+        }
+
+        let oracle_quorum = token_args.oracle_quorum.unwrap_or(price_oracle_account_ids.len() as u32 / 2 + 1);
+        assert_valid_oracles(&price_oracle_account_ids, oracle_quorum);
+
+        let oracle_feed = token_args.oracle_feed.unwrap_or_default();
+        assert_valid_oracle_feed(&oracle_feed);
 
         let token_decimals = whitelisted_token.metadata.decimals;
 
         assert!(token_decimals > 0, "Missing token decimals");
         assert!(token_args.target_price.0 > 0, "Illegal target price");
 
+        let unlock_deadline: Option<Timestamp> = token_args.unlock_deadline.map(|d| d.into());
+        if let Some(unlock_deadline) = unlock_deadline {
+            assert!(unlock_deadline > env::block_timestamp(), "Unlock deadline must be in the future");
+        }
+
+        let unlocking_duration_sec = token_args.unlocking_duration_sec.unwrap_or(DEFAULT_UNLOCKING_DURATION_SEC);
+        assert!(
+            (MIN_UNLOCKING_DURATION_SEC..=MAX_UNLOCKING_DURATION_SEC).contains(&unlocking_duration_sec),
+            "Unlocking duration must be between {} and {} seconds",
+            MIN_UNLOCKING_DURATION_SEC,
+            MAX_UNLOCKING_DURATION_SEC
+        );
+
+        let min_confirmations = token_args.min_confirmations.unwrap_or(DEFAULT_MIN_CONFIRMATIONS);
+        assert!(
+            (1..=MAX_MIN_CONFIRMATIONS).contains(&min_confirmations),
+            "Minimum confirmations must be between 1 and {}",
+            MAX_MIN_CONFIRMATIONS
+        );
+
+        let max_confirmation_gap_sec = token_args.max_confirmation_gap_sec
+            .unwrap_or_else(|| default_max_confirmation_gap_sec(unlocking_duration_sec));
+        assert!(
+            (MIN_CONFIRMATION_GAP_SEC..=MAX_UNLOCKING_DURATION_SEC).contains(&max_confirmation_gap_sec),
+            "Maximum confirmation gap must be between {} and {} seconds",
+            MIN_CONFIRMATION_GAP_SEC,
+            MAX_UNLOCKING_DURATION_SEC
+        );
+
+        let max_price_age_sec = token_args.max_price_age_sec.unwrap_or(DEFAULT_MAX_PRICE_AGE_SEC);
+        assert!(
+            (MIN_MAX_PRICE_AGE_SEC..=MAX_MAX_PRICE_AGE_SEC).contains(&max_price_age_sec),
+            "Maximum price age must be between {} and {} seconds",
+            MIN_MAX_PRICE_AGE_SEC,
+            MAX_MAX_PRICE_AGE_SEC
+        );
+
+        let early_unwrap_penalty_bps = token_args.early_unwrap_penalty_bps.unwrap_or(0);
+        assert_valid_early_unwrap_penalty(early_unwrap_penalty_bps);
+
+        let guardian_ids: Vec<AccountId> = match token_args.guardian_ids {
+            Some(guardian_ids) => guardian_ids.into_iter().map(|a| a.into()).collect(),
+            None => self.backup_trigger_account_id.clone().into_iter().collect(),
+        };
+        // Guardians can unlock and pause the token for every holder, so only the factory picks them
+        for guardian_id in &guardian_ids {
+            assert!(
+                self.backup_trigger_account_id.as_ref() == Some(guardian_id) || self.whitelisted_guardians.contains(guardian_id),
+                "Guardian {} wasn't whitelisted",
+                guardian_id
+            );
+        }
+        let guardian_threshold = token_args.guardian_threshold.unwrap_or(
+            if guardian_ids.is_empty() { 0 } else { guardian_ids.len() as u32 / 2 + 1 }
+        );
+        assert_valid_guardians(&guardian_ids, guardian_threshold);
+
+        let guardian_approval_period_sec = token_args.guardian_approval_period_sec.unwrap_or(DEFAULT_GUARDIAN_APPROVAL_PERIOD_SEC);
+        assert!(
+            (MIN_GUARDIAN_APPROVAL_PERIOD_SEC..=MAX_GUARDIAN_APPROVAL_PERIOD_SEC).contains(&guardian_approval_period_sec),
+            "Guardian approval period must be between {} and {} seconds",
+            MIN_GUARDIAN_APPROVAL_PERIOD_SEC,
+            MAX_GUARDIAN_APPROVAL_PERIOD_SEC
+        );
+
+        let emergency_unlock_delay_sec = token_args.emergency_unlock_delay_sec.unwrap_or(DEFAULT_EMERGENCY_UNLOCK_DELAY_SEC);
+        assert!(
+            (MIN_EMERGENCY_UNLOCK_DELAY_SEC..=MAX_EMERGENCY_UNLOCK_DELAY_SEC).contains(&emergency_unlock_delay_sec),
+            "Emergency unlock delay must be between {} and {} seconds",
+            MIN_EMERGENCY_UNLOCK_DELAY_SEC,
+            MAX_EMERGENCY_UNLOCK_DELAY_SEC
+        );
+
+        let storage_pool: Balance = token_args.storage_pool.map(|a| a.0).unwrap_or(0);
+        let min_sponsored_deposit: Balance = token_args.min_sponsored_deposit
+            .map(|a| a.0)
+            .unwrap_or_else(|| 10u128.pow(token_decimals as u32));
+
         let mut metadata = whitelisted_token.metadata;
 
         let minimum_unlock_price = Price {
@@ -346,8 +529,12 @@ impl TokenFactory {
             decimals: token_decimals + 4,
         };
 
-        metadata.name = format!("{} at ${}", ticker, price);
-        metadata.symbol = format!("{}@{}", ticker, price);
+        metadata.name = format!("{} {} ${}", ticker, unlock_direction.token_id_separator(), price);
+        metadata.symbol = format!("{}{}{}", ticker, unlock_direction.symbol_separator(), price);
+        if let Some(terms_id) = terms_id {
+            metadata.name = format!("{} #{}", metadata.name, terms_id);
+            metadata.symbol = format!("{}#{}", metadata.symbol, terms_id);
+        }
 
         metadata.assert_valid();
 
@@ -355,10 +542,25 @@ impl TokenFactory {
             locked_token_account_id: token_args.token_id.into(),
             token_id: token_id.clone(),
             meta: metadata,
-            backup_trigger_account_id: Some(BACKUP_TRIGGER_ACCOUNT_ID.into()),
-            price_oracle_account_id: input_price_oracle_account_id,
+            owner_id: self.owner_id.clone(),
+            guardian_ids,
+            guardian_threshold,
+            guardian_approval_period_sec,
+            emergency_unlock_delay_sec,
+            price_oracle_account_ids,
+            oracle_quorum,
+            oracle_feed,
             asset_id: whitelisted_token.asset_id.clone(),
             minimum_unlock_price,
+            unlock_direction,
+            unlock_deadline,
+            unlocking_duration_sec,
+            min_confirmations,
+            max_confirmation_gap_sec,
+            max_price_age_sec,
+            early_unwrap_penalty_bps,
+            penalty_beneficiary_id: token_args.penalty_beneficiary_id.map(|a| a.into()),
+            min_sponsored_deposit,
         };
 
         let account_id = env::predecessor_account_id();
@@ -366,11 +568,11 @@ impl TokenFactory {
         let required_balance = self.get_min_attached_balance(&args);
         let user_balance = self.storage_deposits.get(&account_id).unwrap_or(0);
         assert!(
-            user_balance >= required_balance,
+            user_balance >= required_balance + storage_pool,
             "Not enough required balance"
         );
         self.storage_deposits
-            .insert(&account_id, &(user_balance - required_balance));
+            .insert(&account_id, &(user_balance - required_balance - storage_pool));
 
         let initial_storage_usage = env::storage_usage();
 
@@ -381,11 +583,20 @@ impl TokenFactory {
         );
 
         log!(
-            "Creating token {} with asset {} at price {}",
+            "Creating token {} with asset {} {} price {}",
             token_account_id,
             whitelisted_token.asset_id,
+            unlock_direction.token_id_separator(),
             price
         );
+        Event::CreateToken {
+            token_account_id: token_account_id.clone(),
+            locked_token_account_id: args.locked_token_account_id.clone(),
+            asset_id: args.asset_id.clone(),
+            minimum_unlock_price: args.minimum_unlock_price,
+            unlock_direction: args.unlock_direction,
+            creator_id: account_id,
+        }.emit();
 
         let storage_balance_used =
             Balance::from(env::storage_usage() - initial_storage_usage) * STORAGE_PRICE_PER_BYTE;
@@ -394,7 +605,7 @@ impl TokenFactory {
             .create_account()
             .transfer(required_balance - storage_balance_used)
             .deploy_contract(FT_WASM_CODE.to_vec())
-            .function_call(b"new".to_vec(), serde_json::to_vec(&args).unwrap(), 0, GAS)
+            .function_call(b"new".to_vec(), args.to_init_args(), storage_pool, GAS)
     }
 
     fn format_title(s: String) -> String {
@@ -402,48 +613,6 @@ impl TokenFactory {
     }
 }
 
-pub mod u64_dec_format {
-    use near_sdk::serde::{Deserialize, Deserializer, Serializer};
-    use near_sdk::serde::de;
-
-    pub fn serialize<S>(num: &u64, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&num.to_string())
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        String::deserialize(deserializer)?
-            .parse()
-            .map_err(de::Error::custom)
-    }
-}
-
-pub mod u128_dec_format {
-    use near_sdk::serde::{Deserialize, Deserializer, Serializer};
-    use near_sdk::serde::de;
-
-    pub fn serialize<S>(num: &u128, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&num.to_string())
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
-        where
-            D: Deserializer<'de>,
-    {
-        String::deserialize(deserializer)?
-            .parse()
-            .map_err(de::Error::custom)
-    }
-}
-
 fn is_valid_symbol(token_id: &str) -> bool {
     for c in token_id.as_bytes() {
         match c {
@@ -454,8 +623,57 @@ fn is_valid_symbol(token_id: &str) -> bool {
     true
 }
 
-/// returns (token_account_id, ticker, price, token_id)
-fn generate_token_namespace (token_args: &TokenArgsInput, whitelisted_token: &WhitelistedToken) -> (TokenAccountId, String, String, String){
+fn default_max_confirmation_gap_sec(unlocking_duration_sec: DurationSec) -> DurationSec {
+    std::cmp::max(unlocking_duration_sec / DEFAULT_CONFIRMATION_GAPS_PER_WINDOW, MIN_CONFIRMATION_GAP_SEC)
+}
+
+/// Lock terms which differ from the defaults, as `name=value`
+fn custom_lock_terms(token_args: &TokenArgsInput) -> Vec<String> {
+    let unlocking_duration_sec = token_args.unlocking_duration_sec.unwrap_or(DEFAULT_UNLOCKING_DURATION_SEC);
+    let custom_terms: Vec<(&str, Option<String>)> = vec![
+        ("unlock_deadline", token_args.unlock_deadline.map(|d| d.0.to_string())),
+        ("unlocking_duration_sec", Some(unlocking_duration_sec)
+            .filter(|d| *d != DEFAULT_UNLOCKING_DURATION_SEC)
+            .map(|d| d.to_string())),
+        ("min_confirmations", token_args.min_confirmations
+            .filter(|c| *c != DEFAULT_MIN_CONFIRMATIONS)
+            .map(|c| c.to_string())),
+        ("max_confirmation_gap_sec", token_args.max_confirmation_gap_sec
+            .filter(|g| *g != default_max_confirmation_gap_sec(unlocking_duration_sec))
+            .map(|g| g.to_string())),
+        ("max_price_age_sec", token_args.max_price_age_sec
+            .filter(|a| *a != DEFAULT_MAX_PRICE_AGE_SEC)
+            .map(|a| a.to_string())),
+        ("early_unwrap_penalty_bps", token_args.early_unwrap_penalty_bps
+            .filter(|p| *p != 0)
+            .map(|p| p.to_string())),
+        ("penalty_beneficiary_id", token_args.penalty_beneficiary_id.as_ref().map(|a| a.to_string())),
+        ("guardian_approval_period_sec", token_args.guardian_approval_period_sec
+            .filter(|p| *p != DEFAULT_GUARDIAN_APPROVAL_PERIOD_SEC)
+            .map(|p| p.to_string())),
+        ("emergency_unlock_delay_sec", token_args.emergency_unlock_delay_sec
+            .filter(|d| *d != DEFAULT_EMERGENCY_UNLOCK_DELAY_SEC)
+            .map(|d| d.to_string())),
+    ];
+    custom_terms
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{}={}", name, value)))
+        .collect()
+}
+
+/// Identifies custom lock terms in the namespace, so a token with custom terms never takes the
+/// name of the token with the default terms at the same price.
+fn lock_terms_id(token_args: &TokenArgsInput) -> Option<String> {
+    let custom_terms = custom_lock_terms(token_args);
+    if custom_terms.is_empty() {
+        return None;
+    }
+    let hash = env::sha256(custom_terms.join(",").as_bytes());
+    Some(hash[..4].iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// returns (token_account_id, ticker, price, token_id, unlock_direction, terms_id)
+fn generate_token_namespace (token_args: &TokenArgsInput, whitelisted_token: &WhitelistedToken) -> (TokenAccountId, String, String, String, UnlockDirection, Option<String>){
     // name of the token we want to create
     let token_name = TokenFactory::format_title(whitelisted_token.metadata.symbol.clone());
 
@@ -477,10 +695,17 @@ fn generate_token_namespace (token_args: &TokenArgsInput, whitelisted_token: &Wh
         format!("{}", target_price_short)
     };
 
-    let token_id = format!(
-        "{}-at-{}-{}",
-        token_name, target_price_short, target_price_remainder_without_trailing_zeros
+    let unlock_direction = token_args.unlock_direction.unwrap_or_default();
+
+    let terms_id = lock_terms_id(token_args);
+
+    let mut token_id = format!(
+        "{}-{}-{}-{}",
+        token_name, unlock_direction.token_id_separator(), target_price_short, target_price_remainder_without_trailing_zeros
     ).to_ascii_lowercase();
+    if let Some(terms_id) = &terms_id {
+        token_id = format!("{}-{}", token_id, terms_id);
+    }
 
     let token_account_id: TokenAccountId = format!("{}.{}", token_id, env::current_account_id());
     assert!(
@@ -488,7 +713,7 @@ fn generate_token_namespace (token_args: &TokenArgsInput, whitelisted_token: &Wh
         "Token Account ID is invalid"
     );
 
-    (token_account_id, ticker, price, token_id)
+    (token_account_id, ticker, price, token_id, unlock_direction, terms_id)
 }
 
 fn remove_trailing_zeros(amount: u128) -> String {
@@ -504,8 +729,276 @@ fn remove_trailing_zeros(amount: u128) -> String {
 
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    pub(crate) fn set_context(predecessor: usize, attached_deposit: Balance) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(predecessor))
+            .attached_deposit(attached_deposit)
+            .build());
+    }
+
+    pub(crate) fn setup_factory() -> TokenFactory {
+        set_context(5, 0);
+        let mut factory = TokenFactory::new(accounts(5), None);
+        factory.whitelist_token_with_metadata(accounts(1), accounts(1), None, FungibleTokenMetadata {
+            spec: "ft-1.0.0".to_string(),
+            name: "NEAR".to_string(),
+            symbol: "NEAR".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        });
+        factory.whitelist_price_oracle(accounts(2));
+        factory
+    }
+
+    /// ID of the token created with `token_args(args)`
+    pub(crate) fn token_id(factory: &TokenFactory, args: &str) -> TokenId {
+        let token_account_id = factory.get_token_name(token_args(args));
+        token_account_id[..token_account_id.len() - accounts(0).as_ref().len() - 1].to_string()
+    }
+
+    pub(crate) fn token_args(args: &str) -> TokenArgsInput {
+        let mut token_args: serde_json::Value = serde_json::from_str(args).unwrap();
+        token_args["token_id"] = accounts(1).to_string().into();
+        token_args["price_oracle_account_ids"] = vec![accounts(2).to_string()].into();
+        serde_json::from_value(token_args).unwrap()
+    }
+
+    #[test]
+    pub fn test_token_namespace_by_direction() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000"}"#));
+        factory.create_token(token_args(r#"{"target_price": "50000", "unlock_direction": "Below"}"#));
+
+        let above = factory.get_token("near-at-5-0".to_string()).unwrap();
+        let below = factory.get_token("near-below-5-0".to_string()).unwrap();
+        assert_eq!(above.meta.name, "NEAR at $5");
+        assert_eq!(above.meta.symbol, "NEAR@5");
+        assert_eq!(below.meta.name, "NEAR below $5");
+        assert_eq!(below.meta.symbol, "NEAR<5");
+        assert!(matches!(below.unlock_direction, UnlockDirection::Below));
+        assert_eq!(
+            factory.get_token_name(token_args(r#"{"target_price": "50000", "unlock_direction": "Below"}"#)),
+            format!("near-below-5-0.{}", accounts(0))
+        );
+    }
+
+    #[test]
+    pub fn test_token_name_without_oracles() {
+        let factory = setup_factory();
+        let token_args: TokenArgsInput = serde_json::from_str(&format!(
+            r#"{{"token_id": "{}", "target_price": "50000"}}"#,
+            accounts(1)
+        )).unwrap();
+        assert_eq!(factory.get_token_name(token_args), format!("near-at-5-0.{}", accounts(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Price Oracle Contract is missing")]
+    pub fn test_create_token_without_oracles() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        let mut token_args = token_args(r#"{"target_price": "50000"}"#);
+        token_args.price_oracle_account_ids = vec![];
+        factory.create_token(token_args);
+    }
+
+    #[test]
+    pub fn test_custom_lock_terms_namespace() {
+        let mut factory = setup_factory();
+        let custom_args = [
+            r#"{"target_price": "50000", "unlock_deadline": "1000000000000000000"}"#,
+            r#"{"target_price": "50000", "unlocking_duration_sec": 3600}"#,
+            r#"{"target_price": "50000", "early_unwrap_penalty_bps": 5000}"#,
+            r#"{"target_price": "50000", "early_unwrap_penalty_bps": 5000, "penalty_beneficiary_id": "bob"}"#,
+        ];
+        for args in custom_args.iter() {
+            set_context(3, 100 * 10u128.pow(24));
+            factory.create_token(token_args(args));
+        }
+        // Explicit default terms take the canonical name
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(&format!(
+            r#"{{"target_price": "50000", "unlocking_duration_sec": {}, "min_confirmations": {}, "early_unwrap_penalty_bps": 0}}"#,
+            DEFAULT_UNLOCKING_DURATION_SEC, DEFAULT_MIN_CONFIRMATIONS
+        )));
+        assert_eq!(factory.get_number_of_tokens(), 5);
+        assert_eq!(factory.get_token("near-at-5-0".to_string()).unwrap().meta.symbol, "NEAR@5");
+
+        let token_id = token_id(&factory, custom_args[1]);
+        let terms_id = &token_id["near-at-5-0-".len()..];
+        assert_eq!(terms_id.len(), 8);
+        let token = factory.get_token(token_id.clone()).unwrap();
+        assert_eq!(token.meta.name, format!("NEAR at $5 #{}", terms_id));
+        assert_eq!(token.meta.symbol, format!("NEAR@5#{}", terms_id));
+    }
+
+    #[test]
+    pub fn test_unlocking_duration() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000"}"#));
+        let args = r#"{"target_price": "60000", "unlocking_duration_sec": 3600}"#;
+        factory.create_token(token_args(args));
+        assert_eq!(factory.get_token("near-at-5-0".to_string()).unwrap().unlocking_duration_sec, DEFAULT_UNLOCKING_DURATION_SEC);
+        assert_eq!(factory.get_token(token_id(&factory, args)).unwrap().unlocking_duration_sec, 3600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unlocking duration must be between")]
+    pub fn test_unlocking_duration_too_short() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000", "unlocking_duration_sec": 3599}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unlocking duration must be between")]
+    pub fn test_unlocking_duration_too_long() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000", "unlocking_duration_sec": 2592001}"#));
+    }
+
+    #[test]
+    pub fn test_confirmation_defaults() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000"}"#));
+        let token = factory.get_token("near-at-5-0".to_string()).unwrap();
+        assert_eq!(token.min_confirmations, DEFAULT_MIN_CONFIRMATIONS);
+        assert_eq!(token.max_confirmation_gap_sec, 2 * 60 * 60);
+    }
+
+    #[test]
+    pub fn test_min_sponsored_deposit() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000"}"#));
+        factory.create_token(token_args(r#"{"target_price": "60000", "min_sponsored_deposit": "1000"}"#));
+        let token = factory.get_token("near-at-5-0".to_string()).unwrap();
+        assert_eq!(token.min_sponsored_deposit.0, 10u128.pow(24));
+        let token = factory.get_token("near-at-6-0".to_string()).unwrap();
+        assert_eq!(token.min_sponsored_deposit.0, 1000);
+    }
+
+    #[test]
+    pub fn test_whitelisted_guardians() {
+        let mut factory = setup_factory();
+        factory.set_backup_trigger_account_id(Some(accounts(5)));
+        factory.whitelist_guardian(accounts(4));
+        assert_eq!(factory.get_whitelisted_guardians(0, 10), vec![accounts(4).to_string()]);
+        set_context(3, 100 * 10u128.pow(24));
+        let args = format!(
+            r#"{{"target_price": "50000", "guardian_ids": ["{}", "{}"], "guardian_threshold": 2}}"#,
+            accounts(4), accounts(5)
+        );
+        factory.create_token(token_args(&args));
+        let token = factory.get_token(token_id(&factory, &args)).unwrap();
+        assert_eq!(token.guardian_ids, vec![accounts(4).to_string(), accounts(5).to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Guardian bob wasn't whitelisted")]
+    pub fn test_guardian_not_whitelisted() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(&format!(
+            r#"{{"target_price": "50000", "guardian_ids": ["{}"], "guardian_threshold": 1}}"#,
+            accounts(1)
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_whitelist_guardian_by_other_account() {
+        let mut factory = setup_factory();
+        set_context(3, 0);
+        factory.whitelist_guardian(accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate guardian")]
+    pub fn test_duplicate_guardian() {
+        let mut factory = setup_factory();
+        factory.whitelist_guardian(accounts(4));
+        set_context(3, 100 * 10u128.pow(24));
+        let guardian_id = accounts(4);
+        factory.create_token(token_args(&format!(
+            r#"{{"target_price": "50000", "guardian_ids": ["{}", "{}"], "guardian_threshold": 1}}"#,
+            guardian_id, guardian_id
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum confidence must be between 1 and 10000 basis points")]
+    pub fn test_pyth_confidence_too_wide() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(
+            r#"{"target_price": "50000", "oracle_feed": {"Pyth": {"price_id": "c415de8d", "max_confidence_bps": 10001}}}"#
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Maximum price age must be between")]
+    pub fn test_max_price_age_too_long() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000", "max_price_age_sec": 3601}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Early unwrap penalty can't exceed 5000 basis points")]
+    pub fn test_early_unwrap_penalty_too_high() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000", "early_unwrap_penalty_bps": 10000}"#));
+    }
+
+    #[test]
+    pub fn test_unlock_deadline() {
+        let mut factory = setup_factory();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(100 * 10u128.pow(24))
+            .block_timestamp(2000)
+            .build());
+        let args = r#"{"target_price": "50000", "unlock_deadline": "2001"}"#;
+        factory.create_token(token_args(args));
+        assert_eq!(factory.get_token(token_id(&factory, args)).unwrap().unlock_deadline, Some(2001));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unlock deadline must be in the future")]
+    pub fn test_unlock_deadline_in_the_past() {
+        let mut factory = setup_factory();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(100 * 10u128.pow(24))
+            .block_timestamp(2000)
+            .build());
+        factory.create_token(token_args(r#"{"target_price": "50000", "unlock_deadline": "2000"}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token ID near-at-5-0 is already taken")]
+    pub fn test_token_id_taken() {
+        let mut factory = setup_factory();
+        set_context(3, 100 * 10u128.pow(24));
+        factory.create_token(token_args(r#"{"target_price": "50000"}"#));
+        factory.create_token(token_args(r#"{"target_price": "50000", "unlock_direction": "Above"}"#));
+    }
 
     #[test]
     pub fn test_remove_trailing_zeros() {
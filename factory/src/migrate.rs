@@ -1,5 +1,27 @@
 use crate::*;
 
+/// Token args stored by the first version of the factory.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenArgsV0 {
+    pub locked_token_account_id: TokenAccountId,
+    pub token_id: TokenId,
+    pub meta: FungibleTokenMetadata,
+    pub backup_trigger_account_id: Option<AccountId>,
+    pub price_oracle_account_id: AccountId,
+    pub asset_id: AssetId,
+    pub minimum_unlock_price: Price,
+}
+
+/// State of the factory before the ownership was stored.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenFactoryV1 {
+    pub tokens: UnorderedMap<TokenId, TokenArgsV0>,
+    pub storage_deposits: LookupMap<AccountId, Balance>,
+    pub storage_balance_cost: Balance,
+    pub whitelisted_tokens: UnorderedMap<AccountId, WhitelistedToken>,
+    pub whitelisted_price_oracles: UnorderedSet<AccountId>,
+}
+
 #[near_bindgen]
 impl TokenFactory {
     #[private]
@@ -51,7 +73,65 @@ impl TokenFactory {
             storage_deposits: old_contract.storage_deposits,
             storage_balance_cost: old_contract.storage_balance_cost,
             whitelisted_tokens: whitelisted_tokens_new,
-            whitelisted_price_oracles: UnorderedSet::new(StorageKey::WhitelistedPriceOracles)
+            whitelisted_price_oracles: UnorderedSet::new(StorageKey::WhitelistedPriceOracles),
+            whitelisted_guardians: UnorderedSet::new(StorageKey::WhitelistedGuardians),
+            owner_id: env::current_account_id(),
+            proposed_owner_id: None,
+            backup_trigger_account_id: None,
+        }
+    }
+
+    #[private]
+    #[init(ignore_state)]
+    #[allow(dead_code)]
+    pub fn migrate_2(owner_id: ValidAccountId, backup_trigger_account_id: Option<ValidAccountId>) -> Self {
+        let old_contract: TokenFactoryV1 = env::state_read().expect("Old state doesn't exist");
+        let owner_id: AccountId = owner_id.into();
+
+        // Stored token args have the layout of the first version, rewrite them with the defaults
+        // matching the behavior of the tokens deployed by it
+        let mut old_tokens = old_contract.tokens;
+        let old_token_args = old_tokens.to_vec();
+        old_tokens.clear();
+        let mut tokens: UnorderedMap<TokenId, TokenArgs> = UnorderedMap::new(StorageKey::Tokens);
+        for (token_id, args) in old_token_args {
+            let guardian_ids: Vec<AccountId> = args.backup_trigger_account_id.into_iter().collect();
+            tokens.insert(&token_id, &TokenArgs {
+                locked_token_account_id: args.locked_token_account_id,
+                token_id: args.token_id,
+                meta: args.meta,
+                owner_id: owner_id.clone(),
+                guardian_threshold: guardian_ids.len() as u32,
+                guardian_ids,
+                guardian_approval_period_sec: DEFAULT_GUARDIAN_APPROVAL_PERIOD_SEC,
+                emergency_unlock_delay_sec: DEFAULT_EMERGENCY_UNLOCK_DELAY_SEC,
+                price_oracle_account_ids: vec![args.price_oracle_account_id],
+                oracle_quorum: 1,
+                oracle_feed: OracleFeed::NearPriceOracle,
+                asset_id: args.asset_id,
+                minimum_unlock_price: args.minimum_unlock_price,
+                unlock_direction: UnlockDirection::Above,
+                unlock_deadline: None,
+                unlocking_duration_sec: DEFAULT_UNLOCKING_DURATION_SEC,
+                min_confirmations: 1,
+                max_confirmation_gap_sec: DEFAULT_UNLOCKING_DURATION_SEC,
+                max_price_age_sec: DEFAULT_MAX_PRICE_AGE_SEC,
+                early_unwrap_penalty_bps: 0,
+                penalty_beneficiary_id: None,
+                min_sponsored_deposit: 0,
+            });
+        }
+
+        TokenFactory {
+            tokens,
+            storage_deposits: old_contract.storage_deposits,
+            storage_balance_cost: old_contract.storage_balance_cost,
+            whitelisted_tokens: old_contract.whitelisted_tokens,
+            whitelisted_price_oracles: old_contract.whitelisted_price_oracles,
+            whitelisted_guardians: UnorderedSet::new(StorageKey::WhitelistedGuardians),
+            owner_id,
+            proposed_owner_id: None,
+            backup_trigger_account_id: backup_trigger_account_id.map(|a| a.into()),
         }
     }
 
@@ -67,3 +147,55 @@ impl TokenFactory {
         self.whitelisted_tokens.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::set_context;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_migrate_2() {
+        set_context(0, 0);
+        let mut old_contract = TokenFactoryV1 {
+            tokens: UnorderedMap::new(StorageKey::Tokens),
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+            storage_balance_cost: 0,
+            whitelisted_tokens: UnorderedMap::new(StorageKey::WhitelistedTokensV1),
+            whitelisted_price_oracles: UnorderedSet::new(StorageKey::WhitelistedPriceOracles),
+        };
+        old_contract.tokens.insert(&"near-10-0".to_string(), &TokenArgsV0 {
+            locked_token_account_id: accounts(1).into(),
+            token_id: "near-10-0".to_string(),
+            meta: FungibleTokenMetadata {
+                spec: "ft-1.0.0".to_string(),
+                name: "NEAR@10".to_string(),
+                symbol: "NEAR@10".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 24,
+            },
+            backup_trigger_account_id: Some(accounts(4).into()),
+            price_oracle_account_id: accounts(2).into(),
+            asset_id: "wrap.near".to_string(),
+            minimum_unlock_price: Price { multiplier: 100000, decimals: 28 },
+        });
+        env::state_write(&old_contract);
+
+        let mut factory = TokenFactory::migrate_2(accounts(5), None);
+        let token = factory.get_token("near-10-0".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(5).to_string());
+        assert_eq!(token.guardian_ids, vec![accounts(4).to_string()]);
+        assert_eq!(token.guardian_threshold, 1);
+        assert_eq!(token.price_oracle_account_ids, vec![accounts(2).to_string()]);
+        assert_eq!(factory.get_tokens(0, 10).len(), 1);
+        assert_eq!(factory.ft_metadata("near-10-0".to_string()).unwrap().symbol, "NEAR@10");
+
+        set_context(5, 0);
+        let mut meta = token.meta;
+        meta.name = "NEAR at $10".to_string();
+        factory.update_token_metadata("near-10-0".to_string(), meta);
+        assert_eq!(factory.get_token("near-10-0".to_string()).unwrap().meta.name, "NEAR at $10");
+    }
+}
@@ -0,0 +1,134 @@
+use crate::*;
+
+#[near_bindgen]
+impl TokenFactory {
+    /// Proposes a new owner. The ownership is transferred once the new owner accepts it.
+    #[payable]
+    pub fn propose_owner(&mut self, owner_id: Option<ValidAccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.proposed_owner_id = owner_id.map(|a| a.into());
+        Event::OwnerProposed {
+            owner_id: self.owner_id.clone(),
+            proposed_owner_id: self.proposed_owner_id.clone(),
+        }.emit();
+    }
+
+    #[payable]
+    pub fn accept_owner(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert_eq!(Some(&account_id), self.proposed_owner_id.as_ref(), "Not the proposed owner");
+        Event::OwnerChange {
+            old_owner_id: self.owner_id.clone(),
+            new_owner_id: account_id.clone(),
+        }.emit();
+        self.owner_id = account_id;
+        self.proposed_owner_id = None;
+    }
+
+    /// Default guardian of tokens created from now on.
+    pub fn set_backup_trigger_account_id(&mut self, backup_trigger_account_id: Option<ValidAccountId>) {
+        self.assert_owner();
+        self.backup_trigger_account_id = backup_trigger_account_id.map(|a| a.into());
+    }
+
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_proposed_owner_id(&self) -> Option<AccountId> {
+        self.proposed_owner_id.clone()
+    }
+
+    pub fn get_backup_trigger_account_id(&self) -> Option<AccountId> {
+        self.backup_trigger_account_id.clone()
+    }
+}
+
+impl TokenFactory {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "No Access");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{set_context, setup_factory};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_transfer_ownership() {
+        let mut factory = setup_factory();
+
+        set_context(5, 1);
+        factory.propose_owner(Some(accounts(3)));
+        assert_eq!(factory.get_owner_id(), accounts(5).to_string());
+        assert_eq!(factory.get_proposed_owner_id(), Some(accounts(3).to_string()));
+
+        set_context(3, 1);
+        factory.accept_owner();
+        assert_eq!(factory.get_owner_id(), accounts(3).to_string());
+        assert_eq!(factory.get_proposed_owner_id(), None);
+        factory.whitelist_price_oracle(accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_old_owner_loses_access() {
+        let mut factory = setup_factory();
+
+        set_context(5, 1);
+        factory.propose_owner(Some(accounts(3)));
+        set_context(3, 1);
+        factory.accept_owner();
+
+        set_context(5, 0);
+        factory.whitelist_price_oracle(accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not the proposed owner")]
+    pub fn test_accept_owner_by_other_account() {
+        let mut factory = setup_factory();
+
+        set_context(5, 1);
+        factory.propose_owner(Some(accounts(3)));
+        set_context(4, 1);
+        factory.accept_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "Not the proposed owner")]
+    pub fn test_cancel_proposed_owner() {
+        let mut factory = setup_factory();
+
+        set_context(5, 1);
+        factory.propose_owner(Some(accounts(3)));
+        factory.propose_owner(None);
+        assert_eq!(factory.get_proposed_owner_id(), None);
+
+        set_context(3, 1);
+        factory.accept_owner();
+    }
+
+    #[test]
+    pub fn test_backup_trigger_account_id() {
+        let mut factory = setup_factory();
+
+        set_context(5, 0);
+        factory.set_backup_trigger_account_id(Some(accounts(4)));
+        assert_eq!(factory.get_backup_trigger_account_id(), Some(accounts(4).to_string()));
+        factory.set_backup_trigger_account_id(None);
+        assert_eq!(factory.get_backup_trigger_account_id(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_backup_trigger_account_id_by_other_account() {
+        let mut factory = setup_factory();
+
+        set_context(3, 0);
+        factory.set_backup_trigger_account_id(Some(accounts(3)));
+    }
+}
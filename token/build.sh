@@ -2,5 +2,5 @@
 set -e
 
 RUSTFLAGS='-C link-arg=-s' cargo build --target wasm32-unknown-unknown --release
-cp target/wasm32-unknown-unknown/release/locked_ft.wasm ./res/
+cp ../target/wasm32-unknown-unknown/release/locked_ft.wasm ./res/
 
@@ -0,0 +1,84 @@
+use crate::*;
+
+/// Each delivery schedules its own transfer and callback, so the number per call is bounded by gas.
+const MAX_DELIVERIES_PER_CALL: u64 = 10;
+
+#[near_bindgen]
+impl Contract {
+    /// Opts the caller in or out of the automatic delivery of the underlying tokens after unlock.
+    #[payable]
+    pub fn set_auto_delivery(&mut self, enabled: bool) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.ft.accounts.contains_key(&account_id),
+            "The account {} is not registered",
+            account_id
+        );
+        if enabled {
+            self.auto_delivery_accounts.insert(&account_id);
+        } else {
+            self.auto_delivery_accounts.remove(&account_id);
+        }
+    }
+
+    /// Unwraps the whole balance of up to `limit` queued accounts once the token is unlocked.
+    /// Can be called by anyone. A failed transfer only credits back the affected account.
+    /// Returns the number of processed accounts.
+    pub fn process_deliveries(&mut self, limit: u64) -> u64 {
+        assert!(matches!(self.status, Status::Unlocked), "Not unlocked yet");
+        self.assert_not_paused(PausableAction::Unwraps);
+        let limit = std::cmp::min(limit, MAX_DELIVERIES_PER_CALL);
+        let queue = self.auto_delivery_accounts.as_vector();
+        let account_ids: Vec<AccountId> = (0..std::cmp::min(limit, queue.len()))
+            .filter_map(|index| queue.get(index))
+            .collect();
+
+        for account_id in account_ids.iter() {
+            self.auto_delivery_accounts.remove(account_id);
+            let balance = self.ft.accounts.get(account_id).unwrap_or(0);
+            if balance > 0 {
+                let amount = self.internal_unwrap(account_id, account_id, Some(balance));
+                self.internal_send_unwrapped(account_id.clone(), account_id.clone(), amount);
+            }
+        }
+        account_ids.len() as u64
+    }
+
+    pub fn get_auto_delivery(&self, account_id: ValidAccountId) -> bool {
+        self.auto_delivery_accounts.contains(account_id.as_ref())
+    }
+
+    pub fn get_auto_delivery_queue_length(&self) -> u64 {
+        self.auto_delivery_accounts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{set_context, setup_contract};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_process_deliveries() {
+        let mut contract = setup_contract(1);
+        set_context(1, 0);
+        contract.ft.internal_register_account(accounts(2).as_ref());
+        contract.ft.internal_register_account(accounts(3).as_ref());
+        contract.ft_on_transfer(
+            accounts(2),
+            U128(100),
+            r#"{"auto_delivery": true}"#.to_string(),
+        );
+        contract.ft_on_transfer(accounts(3), U128(50), String::new());
+        assert!(contract.get_auto_delivery(accounts(2)));
+        assert!(!contract.get_auto_delivery(accounts(3)));
+
+        contract.status = Status::Unlocked;
+        assert_eq!(contract.process_deliveries(10), 1);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 50);
+        assert_eq!(contract.get_auto_delivery_queue_length(), 0);
+    }
+}
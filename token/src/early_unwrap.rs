@@ -0,0 +1,249 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Burns `amount` of locked tokens (the whole balance by default) before the token is
+    /// unlocked and sends the underlying tokens minus the early unwrap penalty to the caller.
+    /// The penalty goes to the beneficiary, or to the remaining holders if there is none.
+    /// If the transfer fails, the sent amount is credited back, but the penalty is kept.
+    #[payable]
+    pub fn early_unwrap(&mut self, amount: Option<U128>) -> Promise {
+        assert_one_yocto();
+        assert!(
+            self.early_unwrap_penalty_bps > 0,
+            "Early unwrap is disabled"
+        );
+        assert!(
+            !matches!(self.status, Status::Unlocked),
+            "Already unlocked, use unwrap"
+        );
+        self.assert_not_paused(PausableAction::Unwraps);
+        let account_id = env::predecessor_account_id();
+        let amount = amount
+            .map(|a| a.0)
+            .unwrap_or_else(|| self.ft.accounts.get(&account_id).unwrap_or(0));
+        assert!(amount > 0, "Nothing to unwrap");
+
+        let underlying_amount = self.internal_burn(&account_id, amount);
+        let penalty = if self.penalty_beneficiary_id.is_none() && self.ft.total_supply == 0 {
+            // No holders are left to receive the penalty
+            0
+        } else {
+            // Rounded up, so splitting the unwrap into small amounts doesn't avoid the penalty
+            ((U256::from(underlying_amount) * U256::from(self.early_unwrap_penalty_bps)
+                + U256::from(MAX_BPS - 1))
+                / U256::from(MAX_BPS))
+            .as_u128()
+        };
+        if self.penalty_beneficiary_id.is_some() {
+            self.accrued_penalty += penalty;
+        } else {
+            // Increases the amount of underlying tokens backing each locked token
+            self.total_underlying += penalty;
+        }
+        let payout = underlying_amount - penalty;
+        assert!(payout > 0, "The amount is too small to unwrap");
+        Event::EarlyUnwrap {
+            account_id: account_id.clone(),
+            amount: U128(payout),
+            penalty: U128(penalty),
+        }
+        .emit();
+        self.internal_send_unwrapped(account_id.clone(), account_id, payout)
+    }
+
+    /// Sends accrued early unwrap penalties to the beneficiary. Can be called by anyone.
+    pub fn claim_penalty(&mut self) -> Promise {
+        let beneficiary_id = self
+            .penalty_beneficiary_id
+            .clone()
+            .expect("Penalty beneficiary is not set");
+        let amount = self.accrued_penalty;
+        assert!(amount > 0, "No penalty to claim");
+        self.accrued_penalty = 0;
+        self.pending_transfers += amount;
+        ext_fungible_token::ft_transfer(
+            beneficiary_id,
+            U128(amount),
+            Some(format!("Penalty of {}", env::current_account_id())),
+            &self.locked_token_account_id,
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+        )
+        .then(ext_self::after_penalty_transfer(
+            U128(amount),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FT_TRANSFER,
+        ))
+    }
+
+    /// Amount of underlying tokens the account can unwrap.
+    pub fn get_underlying_balance(&self, account_id: ValidAccountId) -> U128 {
+        U128(self.locked_to_underlying(self.ft.accounts.get(account_id.as_ref()).unwrap_or(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{set_context, setup_contract};
+    use crate::tests::{set_promise_result, set_yocto_context};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    pub fn test_early_unwrap_redistribution() {
+        let mut contract = setup_contract(1);
+        contract.early_unwrap_penalty_bps = 1000;
+        set_context(1, 0);
+        for account_id in [accounts(2), accounts(3), accounts(4)].iter() {
+            contract.ft.internal_register_account(account_id.as_ref());
+        }
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        contract.ft_on_transfer(accounts(3), U128(100), String::new());
+
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.early_unwrap(None);
+        assert_eq!(contract.ft_total_supply().0, 100);
+        assert_eq!(contract.total_underlying, 110);
+        assert_eq!(contract.get_underlying_balance(accounts(3)).0, 110);
+
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(4), U128(55), String::new());
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 50);
+        assert_eq!(contract.get_underlying_balance(accounts(4)).0, 55);
+    }
+
+    fn setup_penalty_contract(penalty_bps: u32) -> Contract {
+        let mut contract = setup_contract(1);
+        contract.early_unwrap_penalty_bps = penalty_bps;
+        for account_id in [accounts(2), accounts(3)].iter() {
+            contract.ft.internal_register_account(account_id.as_ref());
+        }
+        contract
+    }
+
+    #[test]
+    pub fn test_share_inflation_refund() {
+        let mut contract = setup_penalty_contract(1000);
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(1000), String::new());
+        set_yocto_context(2, 0);
+        contract.early_unwrap(Some(U128(999)));
+        assert_eq!(contract.ft_total_supply().0, 1);
+        // The penalty of 99.9 is rounded up
+        assert_eq!(contract.total_underlying, 101);
+
+        // Deposits which don't buy a whole locked token are returned
+        set_context(1, 0);
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(3), U128(99), String::new()),
+            PromiseOrValue::Value(U128(99))
+        ));
+        // So is the remainder which doesn't buy another locked token
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(3), U128(199), String::new()),
+            PromiseOrValue::Value(U128(98))
+        ));
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 1);
+        assert_eq!(contract.get_underlying_balance(accounts(2)).0, 101);
+        assert_eq!(contract.get_underlying_balance(accounts(3)).0, 101);
+    }
+
+    #[test]
+    pub fn test_penalty_rounded_up() {
+        let mut contract = setup_penalty_contract(100);
+        contract.penalty_beneficiary_id = Some(accounts(4).into());
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        for _ in 0..10 {
+            set_yocto_context(2, 0);
+            contract.early_unwrap(Some(U128(2)));
+        }
+        // 1% of each chunk of 2 is rounded up to 1
+        assert_eq!(contract.accrued_penalty, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount is too small to unwrap")]
+    pub fn test_early_unwrap_too_small() {
+        let mut contract = setup_penalty_contract(100);
+        contract.penalty_beneficiary_id = Some(accounts(4).into());
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        set_yocto_context(2, 0);
+        contract.early_unwrap(Some(U128(1)));
+    }
+
+    #[test]
+    pub fn test_last_holder_early_unwrap() {
+        let mut contract = setup_penalty_contract(1000);
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        set_yocto_context(2, 0);
+        contract.early_unwrap(None);
+        // No holders are left to receive the penalty
+        assert_eq!(contract.pending_transfers, 100);
+        assert_eq!(contract.total_underlying, 0);
+
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(3), U128(50), String::new());
+        assert_eq!(contract.get_underlying_balance(accounts(3)).0, 50);
+    }
+
+    #[test]
+    pub fn test_penalty_beneficiary() {
+        let mut contract = setup_penalty_contract(1000);
+        contract.penalty_beneficiary_id = Some(accounts(4).into());
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        contract.ft_on_transfer(accounts(3), U128(100), String::new());
+        set_yocto_context(2, 0);
+        contract.early_unwrap(None);
+        assert_eq!(contract.accrued_penalty, 10);
+        assert_eq!(contract.total_underlying, 100);
+        assert_eq!(contract.get_underlying_balance(accounts(3)).0, 100);
+
+        set_context(5, 0);
+        contract.claim_penalty();
+        assert_eq!(contract.accrued_penalty, 0);
+        assert_eq!(contract.pending_transfers, 100);
+
+        // A failed transfer keeps the penalty for the next claim
+        set_promise_result(PromiseResult::Failed);
+        assert!(!contract.after_penalty_transfer(U128(10)));
+        assert_eq!(contract.accrued_penalty, 10);
+        assert_eq!(contract.pending_transfers, 90);
+
+        set_context(5, 0);
+        contract.claim_penalty();
+        set_promise_result(PromiseResult::Successful(vec![]));
+        assert!(contract.after_penalty_transfer(U128(10)));
+        assert_eq!(contract.accrued_penalty, 0);
+        assert_eq!(contract.pending_transfers, 90);
+    }
+
+    #[test]
+    #[should_panic(expected = "No penalty to claim")]
+    pub fn test_claim_no_penalty() {
+        let mut contract = setup_penalty_contract(1000);
+        contract.penalty_beneficiary_id = Some(accounts(4).into());
+        set_context(5, 0);
+        contract.claim_penalty();
+    }
+
+    #[test]
+    #[should_panic(expected = "Early unwrap is disabled")]
+    pub fn test_early_unwrap_disabled() {
+        let mut contract = setup_penalty_contract(0);
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+        set_yocto_context(2, 0);
+        contract.early_unwrap(None);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::*;
+use near_sdk::serde_json;
+
+const EVENT_STANDARD: &str = "locked_ft";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// NEP-297 events emitted by the locked token.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    /// Underlying tokens were locked and locked tokens were minted.
+    Deposit {
+        account_id: AccountId,
+        sender_id: AccountId,
+        amount: U128,
+    },
+    /// Locked tokens were burned and underlying tokens were sent.
+    Unwrap {
+        account_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    },
+    /// Locked tokens were burned before unlock and underlying tokens minus the penalty were sent.
+    EarlyUnwrap {
+        account_id: AccountId,
+        amount: U128,
+        penalty: U128,
+    },
+    /// Sending underlying tokens failed and locked tokens were minted back.
+    UnwrapRefund { account_id: AccountId, amount: U128 },
+    StatusChange {
+        old_status: Status,
+        new_status: Status,
+    },
+    /// A guardian approved an action. `approvals` counts the approvals which haven't expired.
+    GuardianApproval {
+        account_id: AccountId,
+        action: GuardianAction,
+        approvals: u32,
+        guardian_threshold: u32,
+    },
+    /// Guardians reached the threshold. The token can be unlocked after `unlock_timestamp`.
+    EmergencyUnlockSchedule {
+        account_id: AccountId,
+        unlock_timestamp: U64,
+    },
+    /// A guardian canceled the scheduled emergency unlock.
+    EmergencyUnlockCancel { account_id: AccountId },
+    /// The scheduled emergency unlock was finalized.
+    BackupUnlock { account_id: AccountId },
+    GuardianChange {
+        guardian_ids: Vec<AccountId>,
+        guardian_threshold: u32,
+    },
+    OracleChange {
+        price_oracle_account_ids: Vec<AccountId>,
+        oracle_quorum: u32,
+    },
+    OwnerProposed {
+        owner_id: AccountId,
+        proposed_owner_id: Option<AccountId>,
+    },
+    OwnerChange {
+        old_owner_id: AccountId,
+        new_owner_id: AccountId,
+    },
+    PauseChange {
+        account_id: AccountId,
+        pause_state: PauseState,
+    },
+    /// Underlying tokens above the obligations were sent by the owner.
+    SurplusSweep {
+        receiver_id: AccountId,
+        amount: U128,
+    },
+    /// The metadata was synced from the factory.
+    MetadataSync { name: String, symbol: String },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl Event {
+    pub fn emit(&self) {
+        let event_log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+        log!("EVENT_JSON:{}", serde_json::to_string(&event_log).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    pub fn test_event_format() {
+        testing_env!(VMContextBuilder::new().build());
+        Event::Deposit {
+            account_id: "alice.near".to_string(),
+            sender_id: "bob.near".to_string(),
+            amount: U128(100),
+        }
+        .emit();
+        Event::StatusChange {
+            old_status: Status::Locked,
+            new_status: Status::Unlocked,
+        }
+        .emit();
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"locked_ft","version":"1.0.0","event":"deposit","data":{"account_id":"alice.near","sender_id":"bob.near","amount":"100"}}"#,
+                r#"EVENT_JSON:{"standard":"locked_ft","version":"1.0.0","event":"status_change","data":{"old_status":"Locked","new_status":"Unlocked"}}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::*;
+
+/// Action which is executed once enough guardians approved it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GuardianAction {
+    Unlock,
+    RotateGuardians {
+        guardian_ids: Vec<AccountId>,
+        guardian_threshold: u32,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GuardianApproval {
+    pub guardian_id: AccountId,
+    pub action: GuardianAction,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Approves the backup unlock. Once the guardian threshold is reached, the emergency unlock
+    /// is scheduled and can be finalized after `emergency_unlock_delay_sec`.
+    #[payable]
+    pub fn unlock(&mut self) {
+        assert_one_yocto();
+        assert!(!matches!(self.status, Status::Unlocked), "Already unlocked");
+        assert!(
+            self.emergency_unlock_timestamp.is_none(),
+            "Emergency unlock is already scheduled"
+        );
+        self.internal_guardian_approve(GuardianAction::Unlock);
+    }
+
+    /// Unlocks the token once the scheduled emergency unlock is due. Can be called by anyone.
+    pub fn finalize_emergency_unlock(&mut self) {
+        let unlock_timestamp = self
+            .emergency_unlock_timestamp
+            .expect("Emergency unlock is not scheduled");
+        assert!(
+            env::block_timestamp() >= unlock_timestamp,
+            "Emergency unlock is not due yet"
+        );
+        assert!(!matches!(self.status, Status::Unlocked), "Already unlocked");
+        Event::BackupUnlock {
+            account_id: env::predecessor_account_id(),
+        }
+        .emit();
+        self.internal_set_status(Status::Unlocked);
+    }
+
+    /// Cancels the scheduled emergency unlock. Any single guardian can cancel it.
+    #[payable]
+    pub fn cancel_emergency_unlock(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert!(self.guardian_ids.contains(&account_id), "Not a guardian");
+        assert!(
+            self.emergency_unlock_timestamp.take().is_some(),
+            "Emergency unlock is not scheduled"
+        );
+        Event::EmergencyUnlockCancel { account_id }.emit();
+    }
+
+    pub fn get_emergency_unlock_timestamp(&self) -> Option<U64> {
+        self.emergency_unlock_timestamp.map(|t| t.into())
+    }
+
+    /// Approves replacing the guardian set. The set is replaced once the current guardian
+    /// threshold is reached.
+    #[payable]
+    pub fn rotate_guardians(&mut self, guardian_ids: Vec<ValidAccountId>, guardian_threshold: u32) {
+        assert_one_yocto();
+        let guardian_ids: Vec<AccountId> = guardian_ids.into_iter().map(|a| a.into()).collect();
+        assert_valid_guardians(&guardian_ids, guardian_threshold);
+        self.internal_guardian_approve(GuardianAction::RotateGuardians {
+            guardian_ids,
+            guardian_threshold,
+        });
+    }
+
+    /// Approvals which haven't expired yet.
+    pub fn get_guardian_approvals(&self) -> Vec<GuardianApproval> {
+        let expiration = to_nano(self.guardian_approval_period_sec);
+        self.guardian_approvals
+            .iter()
+            .filter(|approval| approval.timestamp + expiration > env::block_timestamp())
+            .cloned()
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Records the approval of the caller and executes the action once the threshold is reached.
+    fn internal_guardian_approve(&mut self, action: GuardianAction) {
+        let guardian_id = env::predecessor_account_id();
+        assert!(self.guardian_ids.contains(&guardian_id), "Not a guardian");
+        self.guardian_approvals = self.get_guardian_approvals();
+        self.guardian_approvals
+            .retain(|approval| approval.guardian_id != guardian_id || approval.action != action);
+        self.guardian_approvals.push(GuardianApproval {
+            guardian_id: guardian_id.clone(),
+            action: action.clone(),
+            timestamp: env::block_timestamp(),
+        });
+        let approvals = self
+            .guardian_approvals
+            .iter()
+            .filter(|approval| approval.action == action)
+            .count() as u32;
+        Event::GuardianApproval {
+            account_id: guardian_id.clone(),
+            action: action.clone(),
+            approvals,
+            guardian_threshold: self.guardian_threshold,
+        }
+        .emit();
+        if approvals < self.guardian_threshold {
+            return;
+        }
+        match action {
+            GuardianAction::Unlock => {
+                self.guardian_approvals
+                    .retain(|approval| approval.action != GuardianAction::Unlock);
+                let unlock_timestamp =
+                    env::block_timestamp() + to_nano(self.emergency_unlock_delay_sec);
+                self.emergency_unlock_timestamp = Some(unlock_timestamp);
+                Event::EmergencyUnlockSchedule {
+                    account_id: guardian_id,
+                    unlock_timestamp: unlock_timestamp.into(),
+                }
+                .emit();
+            }
+            GuardianAction::RotateGuardians {
+                guardian_ids,
+                guardian_threshold,
+            } => {
+                // Approvals of the previous guardian set are no longer valid
+                self.guardian_approvals.clear();
+                self.guardian_ids = guardian_ids;
+                self.guardian_threshold = guardian_threshold;
+                Event::GuardianChange {
+                    guardian_ids: self.guardian_ids.clone(),
+                    guardian_threshold,
+                }
+                .emit();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{set_context, setup_contract, SEC};
+    use crate::tests::set_yocto_context;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_guardian_unlock() {
+        let mut contract = setup_contract(1);
+        contract.guardian_ids = vec![accounts(3).into(), accounts(4).into(), accounts(5).into()];
+        contract.guardian_threshold = 2;
+
+        set_yocto_context(3, 0);
+        contract.unlock();
+        contract.unlock();
+        assert!(matches!(contract.status, Status::Locked));
+
+        // The first approval expires before the second one arrives
+        set_yocto_context(4, 3600 * SEC);
+        contract.unlock();
+        assert!(matches!(contract.status, Status::Locked));
+        assert_eq!(contract.get_guardian_approvals().len(), 1);
+
+        set_yocto_context(5, 3600 * SEC);
+        contract.unlock();
+        assert!(matches!(contract.status, Status::Locked));
+        assert!(contract.get_guardian_approvals().is_empty());
+        assert_eq!(
+            contract.get_emergency_unlock_timestamp(),
+            Some(U64(7200 * SEC))
+        );
+
+        set_context(1, 7200 * SEC);
+        contract.finalize_emergency_unlock();
+        assert!(matches!(contract.status, Status::Unlocked));
+        assert_eq!(contract.get_emergency_unlock_timestamp(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Emergency unlock is not due yet")]
+    pub fn test_emergency_unlock_not_due() {
+        let mut contract = setup_contract(1);
+        contract.guardian_ids = vec![accounts(3).into()];
+        contract.guardian_threshold = 1;
+        set_yocto_context(3, 0);
+        contract.unlock();
+        set_context(1, 3599 * SEC);
+        contract.finalize_emergency_unlock();
+    }
+
+    #[test]
+    #[should_panic(expected = "Emergency unlock is not scheduled")]
+    pub fn test_cancel_emergency_unlock() {
+        let mut contract = setup_contract(1);
+        contract.guardian_ids = vec![accounts(3).into(), accounts(4).into()];
+        contract.guardian_threshold = 1;
+        set_yocto_context(3, 0);
+        contract.unlock();
+        set_yocto_context(4, 0);
+        contract.cancel_emergency_unlock();
+        set_context(1, 3600 * SEC);
+        contract.finalize_emergency_unlock();
+    }
+
+    #[test]
+    pub fn test_rotate_guardians() {
+        let mut contract = setup_contract(1);
+        contract.guardian_ids = vec![accounts(3).into(), accounts(4).into()];
+        contract.guardian_threshold = 2;
+
+        set_yocto_context(3, 0);
+        contract.unlock();
+        contract.rotate_guardians(vec![accounts(5)], 1);
+        set_yocto_context(4, 0);
+        contract.rotate_guardians(vec![accounts(5)], 1);
+        assert_eq!(contract.guardian_ids, vec![accounts(5).to_string()]);
+        assert!(contract.get_guardian_approvals().is_empty());
+
+        set_yocto_context(5, 0);
+        contract.unlock();
+        assert!(contract.get_emergency_unlock_timestamp().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not a guardian")]
+    pub fn test_unlock_not_guardian() {
+        let mut contract = setup_contract(1);
+        set_yocto_context(3, 0);
+        contract.unlock();
+    }
+}
@@ -1,41 +1,67 @@
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
 use near_contract_standards::fungible_token::FungibleToken;
 use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_contract_standards::storage_management::StorageManagement;
 use near_sdk::{
     AccountId, assert_one_yocto, Balance, BorshStorageKey, env, ext_contract, Gas, is_promise_success,
-    log, near_bindgen, PanicOnDefault, Promise, PromiseOrValue, Timestamp,
+    log, near_bindgen, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, serde_json, Timestamp,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::{U128, ValidAccountId};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64, ValidAccountId};
 use near_sdk::serde::{Deserialize, Serialize};
 
+use locked_ft_common::*;
+
+use crate::events::*;
+use crate::guardians::*;
+use crate::oracle_adapter::*;
+use crate::pause::*;
+use crate::price_history::*;
 use crate::price_receiver::*;
+use crate::reconcile::*;
 
-mod price_receiver;
+mod delivery;
+mod early_unwrap;
+mod events;
+mod guardians;
+mod lock_info;
+mod oracle_adapter;
+mod owner;
+mod pause;
+mod price_history;
+pub mod price_receiver;
+mod reconcile;
+mod storage;
 
 near_sdk::setup_alloc!();
 
-const OWNER_ID: &str = "dreamproject.near";
 const NO_DEPOSIT: Balance = 0;
 const ONE_YOCTO: Balance = 1;
 
 const TGAS: Gas = 1_000_000_000_000;
 const GAS_FOR_FT_TRANSFER: Gas = 10 * TGAS;
 const GAS_FOR_AFTER_FT_TRANSFER: Gas = 10 * TGAS;
+const GAS_FOR_FT_TRANSFER_CALL: Gas = 60 * TGAS;
+const GAS_FOR_AFTER_FT_TRANSFER_CALL: Gas = 10 * TGAS;
 const GAS_FT_METADATA_READ: Gas = 25 * TGAS;
 const GAS_FT_METADATA_WRITE: Gas = 25 * TGAS;
 
-type TokenId = String;
 pub type TokenAccountId = AccountId;
 
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     Ft,
     FtMeta,
+    AutoDeliveryAccounts,
+    SponsoredAccounts,
+    PriceHistory,
+    PendingUnwraps,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone)]
@@ -45,22 +71,58 @@ pub enum Status {
     Unlocking {
         #[serde(with = "u64_dec_format")]
         initiated_timestamp: Timestamp,
+        /// Oracle timestamp of the latest report counted towards unlocking
+        #[serde(with = "u64_dec_format")]
+        last_report_timestamp: Timestamp,
+        /// Number of confirming reports received after unlocking has started
+        confirmations: u32,
     },
     Unlocked,
 }
 
+/// Optional JSON `msg` of `ft_transfer_call` on the underlying token.
+#[derive(Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositMsg {
+    /// Account credited with the locked tokens. Defaults to the sender.
+    pub receiver_id: Option<ValidAccountId>,
+    /// Deliver the underlying tokens automatically once the token is unlocked.
+    #[serde(default)]
+    pub auto_delivery: bool,
+}
+
 #[ext_contract(ext_self)]
 pub trait ExtSelf {
     fn after_ft_transfer(&mut self, account_id: AccountId, balance: U128) -> bool;
 
+    fn after_ft_transfer_call(&mut self, account_id: AccountId, balance: U128) -> U128;
+
+    fn after_penalty_transfer(&mut self, amount: U128) -> bool;
+
+    fn after_sweep_transfer(&mut self, amount: U128) -> bool;
+
     // Save FT metadata
     fn on_ft_metadata(
         &mut self
     );
+
+    fn on_reconcile(&mut self) -> Reconciliation;
+
+    fn on_sweep_balance(
+        &mut self,
+        receiver_id: AccountId,
+        amount: Option<U128>,
+    ) -> PromiseOrValue<bool>;
 }
 
 pub trait ExtSelf {
     fn after_ft_transfer(&mut self, account_id: AccountId, balance: U128) -> bool;
+
+    fn after_ft_transfer_call(&mut self, account_id: AccountId, balance: U128) -> U128;
+
+    fn after_penalty_transfer(&mut self, amount: U128) -> bool;
+
+    fn after_sweep_transfer(&mut self, amount: U128) -> bool;
 }
 
 #[ext_contract(ext_ft)]
@@ -70,25 +132,131 @@ pub trait ExtFT {
 }
 
 #[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault, Serialize)]
-#[serde(crate = "near_sdk::serde")]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
-    #[serde(skip)]
     pub ft: FungibleToken,
     pub token_id: TokenId,
-    #[serde(skip)]
     pub meta: LazyOption<FungibleTokenMetadata>,
-    pub backup_trigger_account_id: Option<AccountId>,
-    pub price_oracle_account_id: AccountId,
+    pub owner_id: AccountId,
+    pub proposed_owner_id: Option<AccountId>,
+    /// Accounts which can unlock the token without the price condition
+    pub guardian_ids: Vec<AccountId>,
+    /// Number of guardian approvals required to execute a guardian action
+    pub guardian_threshold: u32,
+    /// Guardian approvals expire after this period
+    pub guardian_approval_period_sec: DurationSec,
+    pub guardian_approvals: Vec<GuardianApproval>,
+    /// Delay between guardians approving the backup unlock and the token unlocking
+    pub emergency_unlock_delay_sec: DurationSec,
+    /// Time after which the scheduled emergency unlock can be finalized
+    pub emergency_unlock_timestamp: Option<Timestamp>,
+    pub price_oracle_account_ids: Vec<AccountId>,
+    /// Number of fresh oracle reports required to make an unlock decision
+    pub oracle_quorum: u32,
+    pub oracle_reports: Vec<OracleReport>,
+    /// Format of the reports accepted from the price oracles
+    pub oracle_feed: OracleFeed,
     pub asset_id: AssetId,
     pub minimum_unlock_price: Price,
+    pub unlock_direction: UnlockDirection,
+    pub unlock_deadline: Option<Timestamp>,
+    pub unlocking_duration_sec: DurationSec,
+    pub min_confirmations: u32,
+    pub max_confirmation_gap_sec: DurationSec,
+    pub max_price_age_sec: DurationSec,
     pub locked_token_account_id: TokenAccountId,
     pub factory_account_id: AccountId,
     pub status: Status,
+    /// Underlying tokens backing the total supply, including penalties redistributed to holders
+    pub total_underlying: Balance,
+    /// Penalty of `early_unwrap` in basis points, 0 disables early unwrap
+    pub early_unwrap_penalty_bps: u32,
+    /// Receives penalties of `early_unwrap`. If not set, penalties are redistributed to holders
+    pub penalty_beneficiary_id: Option<AccountId>,
+    /// Penalties which were not claimed by the beneficiary yet
+    pub accrued_penalty: Balance,
+    /// Accounts which opted in to receive the underlying tokens without calling `unwrap`
+    pub auto_delivery_accounts: UnorderedSet<AccountId>,
+    pub pause_state: PauseState,
+    /// Pays the storage of depositors which are not registered yet
+    pub storage_pool: Balance,
+    /// Minimum deposit which gets the storage of a new account paid from the pool
+    pub min_sponsored_deposit: Balance,
+    /// Accounts registered from the storage pool
+    pub sponsored_accounts: UnorderedSet<AccountId>,
+    /// Underlying tokens sent by the contract which were not resolved yet
+    pub pending_transfers: Balance,
+    /// Number of unwraps in flight per account. The account stays open until they resolve
+    pub pending_unwraps: LookupMap<AccountId, u32>,
+    pub last_reconciliation: Option<Reconciliation>,
+    /// Reconciliation recorded by the first step of the surplus sweep
+    pub sweep_reconciliation: Option<Reconciliation>,
+    /// Ring buffer of accepted oracle reports
+    pub price_history: Vector<PriceRecord>,
+    /// Number of oracle reports ever recorded in the price history
+    pub price_history_count: u64,
+    /// Number of accounts with a positive balance, excluding the contract itself
+    pub holder_count: u64,
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>) {
+        self.assert_not_paused(PausableAction::Transfers);
+        let account_ids = [env::predecessor_account_id(), receiver_id.to_string()];
+        self.internal_track_holders(&account_ids, |contract| {
+            contract.ft.ft_transfer(receiver_id, amount, memo)
+        })
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.assert_not_paused(PausableAction::Transfers);
+        let account_ids = [env::predecessor_account_id(), receiver_id.to_string()];
+        self.internal_track_holders(&account_ids, |contract| {
+            contract.ft.ft_transfer_call(receiver_id, amount, memo, msg)
+        })
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.ft.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: ValidAccountId) -> U128 {
+        self.ft.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    #[private]
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: ValidAccountId,
+        receiver_id: ValidAccountId,
+        amount: U128,
+    ) -> U128 {
+        let sender_id: AccountId = sender_id.into();
+        let account_ids = [sender_id.clone(), receiver_id.to_string()];
+        let (used_amount, burned_amount) = self.internal_track_holders(&account_ids, |contract| {
+            contract
+                .ft
+                .internal_ft_resolve_transfer(&sender_id, receiver_id, amount)
+        });
+        if burned_amount > 0 {
+            self.on_tokens_burned(sender_id, burned_amount);
+        }
+        used_amount.into()
+    }
 }
 
-near_contract_standards::impl_fungible_token_core!(Contract, ft, on_tokens_burned);
-near_contract_standards::impl_fungible_token_storage!(Contract, ft, on_account_closed);
 
 #[near_bindgen]
 impl FungibleTokenMetadataProvider for Contract {
@@ -99,20 +267,83 @@ impl FungibleTokenMetadataProvider for Contract {
 
 #[near_bindgen]
 impl FungibleTokenReceiver for Contract {
-    #[allow(unused_variables)]
+    /// Locks the underlying tokens and credits locked tokens to the sender, or to the
+    /// `receiver_id` of the JSON `msg`. Invalid messages and unregistered receivers are refunded.
     fn ft_on_transfer(
         &mut self,
         sender_id: ValidAccountId,
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
+        if env::predecessor_account_id() == env::current_account_id() {
+            return self.internal_redeem(sender_id.into(), amount.0);
+        }
         assert_eq!(
             &env::predecessor_account_id(),
             &self.locked_token_account_id
         );
         assert!(matches!(self.status, Status::Locked));
-        self.ft.internal_deposit(sender_id.as_ref(), amount.0);
-        return PromiseOrValue::Value(U128(0));
+        self.assert_not_paused(PausableAction::Deposits);
+        let sender_id: AccountId = sender_id.into();
+        let deposit_msg: DepositMsg = if msg.is_empty() {
+            DepositMsg::default()
+        } else if let Ok(deposit_msg) = serde_json::from_str(&msg) {
+            deposit_msg
+        } else {
+            log!("Invalid deposit msg, returning {} to {}", amount.0, sender_id);
+            return PromiseOrValue::Value(amount);
+        };
+        let account_id: AccountId = deposit_msg
+            .receiver_id
+            .map(|a| a.into())
+            .unwrap_or_else(|| sender_id.clone());
+        if account_id == env::current_account_id() {
+            // Locked tokens of the contract itself are being redeemed, nobody could unwrap them
+            log!("Invalid receiver {}, returning {} to {}", account_id, amount.0, sender_id);
+            return PromiseOrValue::Value(amount);
+        }
+        let locked_amount = self.underlying_to_locked(amount.0);
+        if locked_amount == 0 {
+            log!(
+                "The deposit is worth less than 1 locked token, returning {} to {}",
+                amount.0,
+                sender_id
+            );
+            return PromiseOrValue::Value(amount);
+        }
+        // The remainder which doesn't buy a whole locked token is returned
+        let used_amount = self.locked_to_underlying_rounded_up(locked_amount);
+        if !self.ft.accounts.contains_key(&account_id) {
+            if amount.0 < self.min_sponsored_deposit {
+                log!(
+                    "The account {} is not registered and the deposit is too small to pay its storage from the pool, returning {} to {}",
+                    account_id,
+                    amount.0,
+                    sender_id
+                );
+                return PromiseOrValue::Value(amount);
+            }
+            if !self.internal_register_sponsored(&account_id) {
+                log!(
+                    "The account {} is not registered and the storage pool is empty, returning {} to {}",
+                    account_id,
+                    amount.0,
+                    sender_id
+                );
+                return PromiseOrValue::Value(amount);
+            }
+        }
+        self.internal_mint(&account_id, used_amount);
+        if deposit_msg.auto_delivery {
+            self.auto_delivery_accounts.insert(&account_id);
+        }
+        Event::Deposit {
+            account_id,
+            sender_id,
+            amount: U128(used_amount),
+        }
+        .emit();
+        PromiseOrValue::Value(U128(amount.0 - used_amount))
     }
 }
 
@@ -121,16 +352,50 @@ impl ExtSelf for Contract {
     #[private]
     fn after_ft_transfer(&mut self, account_id: AccountId, balance: U128) -> bool {
         let promise_success = is_promise_success();
-        if promise_success {
-            if let Some(balance) = self.ft.accounts.get(&account_id) {
-                if balance == 0 {
-                    self.ft.accounts.remove(&account_id);
-                    Promise::new(account_id).transfer(self.storage_balance_bounds().min.0);
+        self.pending_transfers -= balance.0;
+        if !promise_success {
+            log!("Failed to transfer {} to account {}", balance.0, account_id);
+        }
+        self.internal_resolve_unwrap(account_id, if promise_success { 0 } else { balance.0 });
+        promise_success
+    }
+
+    /// Re-credits the amount which wasn't used by the receiver of `ft_transfer_call`.
+    /// Returns the used amount.
+    #[private]
+    fn after_ft_transfer_call(&mut self, account_id: AccountId, balance: U128) -> U128 {
+        let used = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                if let Ok(used) = serde_json::from_slice::<U128>(&value) {
+                    std::cmp::min(balance.0, used.0)
+                } else {
+                    balance.0
                 }
             }
-        } else {
-            log!("Failed to transfer {} to account {}", account_id, balance.0);
-            self.ft.internal_deposit(&account_id, balance.into());
+            _ => 0,
+        };
+        self.pending_transfers -= balance.0;
+        self.internal_resolve_unwrap(account_id, balance.0 - used);
+        U128(used)
+    }
+
+    #[private]
+    fn after_penalty_transfer(&mut self, amount: U128) -> bool {
+        let promise_success = is_promise_success();
+        self.pending_transfers -= amount.0;
+        if !promise_success {
+            log!("Failed to transfer penalty {}", amount.0);
+            self.accrued_penalty += amount.0;
+        }
+        promise_success
+    }
+
+    #[private]
+    fn after_sweep_transfer(&mut self, amount: U128) -> bool {
+        let promise_success = is_promise_success();
+        self.pending_transfers -= amount.0;
+        if !promise_success {
+            log!("Failed to sweep surplus {}", amount.0);
         }
         promise_success
     }
@@ -138,65 +403,131 @@ impl ExtSelf for Contract {
 
 #[near_bindgen]
 impl Contract {
+    /// The attached deposit funds the storage pool.
     #[init]
-    pub fn new(
-        locked_token_account_id: ValidAccountId,
-        token_id: TokenAccountId,
-        meta: FungibleTokenMetadata,
-        backup_trigger_account_id: Option<ValidAccountId>,
-        price_oracle_account_id: ValidAccountId,
-        asset_id: AssetId,
-        minimum_unlock_price: Price,
-    ) -> Self {
-        Self {
+    #[payable]
+    pub fn new(args: TokenArgs) -> Self {
+        let account_ids = args
+            .guardian_ids
+            .iter()
+            .chain(args.price_oracle_account_ids.iter())
+            .chain(args.penalty_beneficiary_id.iter())
+            .chain(vec![&args.locked_token_account_id, &args.owner_id]);
+        for account_id in account_ids {
+            assert!(
+                env::is_valid_account_id(account_id.as_bytes()),
+                "Invalid account ID {}",
+                account_id
+            );
+        }
+        assert_valid_oracles(&args.price_oracle_account_ids, args.oracle_quorum);
+        assert_valid_oracle_feed(&args.oracle_feed);
+        assert_valid_guardians(&args.guardian_ids, args.guardian_threshold);
+        assert_valid_early_unwrap_penalty(args.early_unwrap_penalty_bps);
+        let mut this = Self {
             ft: FungibleToken::new(StorageKey::Ft),
-            token_id,
-            meta: LazyOption::new(StorageKey::FtMeta, Some(&meta)),
-            backup_trigger_account_id: backup_trigger_account_id.map(|a| a.into()),
-            locked_token_account_id: locked_token_account_id.into(),
+            token_id: args.token_id,
+            meta: LazyOption::new(StorageKey::FtMeta, Some(&args.meta)),
+            owner_id: args.owner_id,
+            proposed_owner_id: None,
+            guardian_ids: args.guardian_ids,
+            guardian_threshold: args.guardian_threshold,
+            guardian_approval_period_sec: args.guardian_approval_period_sec,
+            guardian_approvals: vec![],
+            emergency_unlock_delay_sec: args.emergency_unlock_delay_sec,
+            emergency_unlock_timestamp: None,
+            locked_token_account_id: args.locked_token_account_id,
             status: Status::Locked,
-            price_oracle_account_id: price_oracle_account_id.into(),
-            asset_id,
-            minimum_unlock_price,
-            factory_account_id: env::predecessor_account_id()
-        }
+            price_oracle_account_ids: args.price_oracle_account_ids,
+            oracle_quorum: args.oracle_quorum,
+            oracle_reports: vec![],
+            oracle_feed: args.oracle_feed,
+            asset_id: args.asset_id,
+            minimum_unlock_price: args.minimum_unlock_price,
+            unlock_direction: args.unlock_direction,
+            unlock_deadline: args.unlock_deadline,
+            unlocking_duration_sec: args.unlocking_duration_sec,
+            min_confirmations: args.min_confirmations,
+            max_confirmation_gap_sec: args.max_confirmation_gap_sec,
+            max_price_age_sec: args.max_price_age_sec,
+            factory_account_id: env::predecessor_account_id(),
+            total_underlying: 0,
+            early_unwrap_penalty_bps: args.early_unwrap_penalty_bps,
+            penalty_beneficiary_id: args.penalty_beneficiary_id,
+            accrued_penalty: 0,
+            auto_delivery_accounts: UnorderedSet::new(StorageKey::AutoDeliveryAccounts),
+            pause_state: PauseState::default(),
+            storage_pool: env::attached_deposit(),
+            min_sponsored_deposit: args.min_sponsored_deposit,
+            sponsored_accounts: UnorderedSet::new(StorageKey::SponsoredAccounts),
+            pending_transfers: 0,
+            pending_unwraps: LookupMap::new(StorageKey::PendingUnwraps),
+            last_reconciliation: None,
+            sweep_reconciliation: None,
+            price_history: Vector::new(StorageKey::PriceHistory),
+            price_history_count: 0,
+            holder_count: 0,
+        };
+        // Locked tokens transferred to the contract itself are redeemed
+        this.ft.internal_register_account(&env::current_account_id());
+        this
     }
 
-    pub fn get_info(self) -> Self {
-        self
+    /// Unlocks the token once the unlock deadline has passed. Can be called by anyone.
+    pub fn unlock_after_deadline(&mut self) {
+        let unlock_deadline = self.unlock_deadline.expect("Unlock deadline is not set");
+        assert!(
+            env::block_timestamp() >= unlock_deadline,
+            "Unlock deadline is not reached"
+        );
+        assert!(!matches!(self.status, Status::Unlocked), "Already unlocked");
+        log!("Unlocked after deadline {}", unlock_deadline);
+        self.internal_set_status(Status::Unlocked);
     }
 
+    /// Burns `amount` of locked tokens (the whole balance by default) and sends the same amount
+    /// of underlying tokens to `receiver_id` (the caller by default).
     #[payable]
-    pub fn unlock(&mut self) {
+    pub fn unwrap(&mut self, amount: Option<U128>, receiver_id: Option<ValidAccountId>) -> Promise {
         assert_one_yocto();
-        assert_eq!(
-            &Some(env::predecessor_account_id()),
-            &self.backup_trigger_account_id
-        );
-        assert!(!matches!(self.status, Status::Unlocked));
-        self.status = Status::Unlocked;
+        let account_id = env::predecessor_account_id();
+        let receiver_id: AccountId = receiver_id
+            .map(|a| a.into())
+            .unwrap_or_else(|| account_id.clone());
+        let amount = self.internal_unwrap(&account_id, &receiver_id, amount.map(|a| a.0));
+        self.internal_send_unwrapped(account_id, receiver_id, amount)
     }
 
+    /// Burns `amount` of locked tokens (the whole balance by default) and sends the same amount
+    /// of underlying tokens to `receiver_id` with `ft_transfer_call`. The amount not used by the
+    /// receiver is credited back to the caller as locked tokens.
     #[payable]
-    pub fn unwrap(&mut self) -> Promise {
+    pub fn unwrap_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        amount: Option<U128>,
+        msg: String,
+    ) -> Promise {
         assert_one_yocto();
-        assert!(matches!(self.status, Status::Unlocked));
         let account_id = env::predecessor_account_id();
-        let balance = self.ft.accounts.get(&account_id).unwrap_or(0);
-        self.ft.internal_withdraw(&account_id, balance);
-        ext_fungible_token::ft_transfer(
-            account_id.clone(),
-            U128(balance),
+        let receiver_id: AccountId = receiver_id.into();
+        let amount = self.internal_unwrap(&account_id, &receiver_id, amount.map(|a| a.0));
+        self.pending_transfers += amount;
+        self.internal_add_pending_unwrap(&account_id);
+        ext_fungible_token::ft_transfer_call(
+            receiver_id,
+            U128(amount),
             Some(format!("Unwrapping {} tokens", env::current_account_id())),
+            msg,
             &self.locked_token_account_id,
             ONE_YOCTO,
-            GAS_FOR_FT_TRANSFER,
-        ).then(ext_self::after_ft_transfer(
+            GAS_FOR_FT_TRANSFER_CALL,
+        ).then(ext_self::after_ft_transfer_call(
             account_id,
-            U128(balance),
+            U128(amount),
             &env::current_account_id(),
             NO_DEPOSIT,
-            GAS_FOR_AFTER_FT_TRANSFER,
+            GAS_FOR_AFTER_FT_TRANSFER_CALL,
         ))
     }
 
@@ -215,19 +546,223 @@ impl Contract {
     }
 
 
-    pub fn update_price_oracle_account_id(&mut self, price_oracle_account_id: ValidAccountId) {
-        assert_owner();
-        self.price_oracle_account_id = price_oracle_account_id.into();
+    pub fn update_price_oracles(
+        &mut self,
+        price_oracle_account_ids: Vec<ValidAccountId>,
+        oracle_quorum: u32,
+    ) {
+        self.assert_owner();
+        let price_oracle_account_ids: Vec<AccountId> =
+            price_oracle_account_ids.into_iter().map(|a| a.into()).collect();
+        assert_valid_oracles(&price_oracle_account_ids, oracle_quorum);
+        self.oracle_reports
+            .retain(|report| price_oracle_account_ids.contains(&report.oracle_id));
+        self.price_oracle_account_ids = price_oracle_account_ids;
+        self.oracle_quorum = oracle_quorum;
+        Event::OracleChange {
+            price_oracle_account_ids: self.price_oracle_account_ids.clone(),
+            oracle_quorum,
+        }
+        .emit();
     }
 
     pub fn get_status(&self) -> Status { self.status }
 
-    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+    /// Amount of underlying tokens backing the given amount of locked tokens.
+    pub(crate) fn locked_to_underlying(&self, amount: Balance) -> Balance {
+        if self.ft.total_supply == 0 {
+            return amount;
+        }
+        (U256::from(amount) * U256::from(self.total_underlying) / U256::from(self.ft.total_supply))
+            .as_u128()
+    }
+
+    /// Amount of locked tokens backed by the given amount of underlying tokens.
+    pub(crate) fn underlying_to_locked(&self, amount: Balance) -> Balance {
+        if self.ft.total_supply == 0 || self.total_underlying == 0 {
+            return amount;
+        }
+        (U256::from(amount) * U256::from(self.ft.total_supply) / U256::from(self.total_underlying))
+            .as_u128()
+    }
+
+    /// Smallest amount of underlying tokens which mints the given amount of locked tokens.
+    pub(crate) fn locked_to_underlying_rounded_up(&self, amount: Balance) -> Balance {
+        if self.ft.total_supply == 0 || self.total_underlying == 0 {
+            return amount;
+        }
+        let supply = U256::from(self.ft.total_supply);
+        ((U256::from(amount) * U256::from(self.total_underlying) + supply - 1) / supply).as_u128()
+    }
+
+    /// Mints locked tokens for the given amount of underlying tokens.
+    pub(crate) fn internal_mint(&mut self, account_id: &AccountId, amount: Balance) {
+        let locked_amount = self.underlying_to_locked(amount);
+        self.internal_track_holders(std::slice::from_ref(account_id), |contract| {
+            contract.ft.internal_deposit(account_id, locked_amount)
+        });
+        self.total_underlying += amount;
+    }
+
+    /// Burns locked tokens. Returns the amount of underlying tokens they were backed by.
+    pub(crate) fn internal_burn(&mut self, account_id: &AccountId, amount: Balance) -> Balance {
+        let underlying_amount = self.locked_to_underlying(amount);
+        self.internal_track_holders(std::slice::from_ref(account_id), |contract| {
+            contract.ft.internal_withdraw(account_id, amount)
+        });
+        self.total_underlying -= underlying_amount;
+        self.internal_release_unbacked_underlying();
+        underlying_amount
+    }
+
+    /// Underlying tokens left once all locked tokens are gone, e.g. rounding leftovers or the
+    /// backing of force-burned tokens, become surplus instead of going to the next depositor.
+    pub(crate) fn internal_release_unbacked_underlying(&mut self) {
+        if self.ft.total_supply == 0 && self.total_underlying > 0 {
+            log!(
+                "Released {} underlying tokens which no longer back locked tokens",
+                self.total_underlying
+            );
+            self.total_underlying = 0;
+        }
+    }
+
+    /// Burns locked tokens of the account before sending the underlying tokens.
+    /// Returns the amount of underlying tokens to send.
+    fn internal_unwrap(
+        &mut self,
+        account_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Option<Balance>,
+    ) -> Balance {
+        assert!(matches!(self.status, Status::Unlocked), "Not unlocked yet");
+        self.assert_not_paused(PausableAction::Unwraps);
+        let amount =
+            amount.unwrap_or_else(|| self.ft.accounts.get(account_id).unwrap_or(0));
+        assert!(amount > 0, "Nothing to unwrap");
+        let underlying_amount = self.internal_burn(account_id, amount);
+        Event::Unwrap {
+            account_id: account_id.clone(),
+            receiver_id: receiver_id.clone(),
+            amount: U128(underlying_amount),
+        }
+        .emit();
+        underlying_amount
+    }
+
+    /// Redeems locked tokens which were sent to this contract with `ft_transfer_call`.
+    /// While the token is not unlocked, the whole amount is returned to the sender.
+    fn internal_redeem(&mut self, sender_id: AccountId, amount: Balance) -> PromiseOrValue<U128> {
+        if !matches!(self.status, Status::Unlocked) {
+            log!("Not unlocked yet, returning {} to {}", amount, sender_id);
+            return PromiseOrValue::Value(U128(amount));
+        }
+        if self.pause_state.is_paused(PausableAction::Unwraps) {
+            log!("Unwraps are paused, returning {} to {}", amount, sender_id);
+            return PromiseOrValue::Value(U128(amount));
+        }
+        let underlying_amount = self.internal_burn(&env::current_account_id(), amount);
+        Event::Unwrap {
+            account_id: sender_id.clone(),
+            receiver_id: sender_id.clone(),
+            amount: U128(underlying_amount),
+        }
+        .emit();
+        self.internal_send_unwrapped(sender_id.clone(), sender_id, underlying_amount);
+        PromiseOrValue::Value(U128(0))
+    }
+
+    /// Sends unwrapped underlying tokens to the receiver. On failure they are credited back
+    /// to the account as locked tokens.
+    fn internal_send_unwrapped(
+        &mut self,
+        account_id: AccountId,
+        receiver_id: AccountId,
+        amount: Balance,
+    ) -> Promise {
+        self.pending_transfers += amount;
+        self.internal_add_pending_unwrap(&account_id);
+        ext_fungible_token::ft_transfer(
+            receiver_id,
+            U128(amount),
+            Some(format!("Unwrapping {} tokens", env::current_account_id())),
+            &self.locked_token_account_id,
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+        ).then(ext_self::after_ft_transfer(
+            account_id,
+            U128(amount),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FT_TRANSFER,
+        ))
+    }
+
+    fn internal_add_pending_unwrap(&mut self, account_id: &AccountId) {
+        let pending = self.pending_unwraps.get(account_id).unwrap_or(0);
+        self.pending_unwraps.insert(account_id, &(pending + 1));
+    }
+
+    /// Returns the number of unwraps of the account which are still in flight.
+    fn internal_remove_pending_unwrap(&mut self, account_id: &AccountId) -> u32 {
+        let pending = self.pending_unwraps.get(account_id).unwrap_or(1) - 1;
+        if pending == 0 {
+            self.pending_unwraps.remove(account_id);
+        } else {
+            self.pending_unwraps.insert(account_id, &pending);
+        }
+        pending
+    }
+
+    /// Credits back the refunded part of an unwrap as locked tokens at the current rate, or releases the storage of the account
+    /// once its balance reaches zero and none of its unwraps are in flight.
+    fn internal_resolve_unwrap(&mut self, account_id: AccountId, refund: Balance) {
+        let pending = self.internal_remove_pending_unwrap(&account_id);
+        if refund > 0 {
+            if !self.ft.accounts.contains_key(&account_id) {
+                // The account was closed by another unwrap in the meantime
+                self.ft.internal_register_account(&account_id);
+            }
+            self.internal_mint(&account_id, refund);
+            Event::UnwrapRefund {
+                account_id,
+                amount: U128(refund),
+            }
+            .emit();
+        } else if pending == 0 && self.ft.accounts.get(&account_id) == Some(0) {
+            self.ft.accounts.remove(&account_id);
+            self.auto_delivery_accounts.remove(&account_id);
+            if !self.internal_release_sponsored(&account_id) {
+                Promise::new(account_id).transfer(self.storage_balance_bounds().min.0);
+            }
+        }
+    }
+
+    pub(crate) fn internal_set_status(&mut self, status: Status) {
+        Event::StatusChange {
+            old_status: self.status,
+            new_status: status,
+        }
+        .emit();
+        self.status = status;
+        if matches!(status, Status::Unlocked) {
+            self.emergency_unlock_timestamp = None;
+        }
+    }
+
+    pub(crate) fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
         log!("Closed @{} with {}", account_id, balance);
+        self.internal_release_unbacked_underlying();
+        if balance > 0 {
+            self.holder_count -= 1;
+        }
+        self.auto_delivery_accounts.remove(&account_id);
+        self.internal_release_sponsored(&account_id);
     }
 
     fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
         log!("Account @{} burned {}", account_id, amount);
+        self.internal_release_unbacked_underlying();
     }
 
     #[private]
@@ -236,6 +771,11 @@ impl Contract {
         #[callback] ft_metadata: Option<FungibleTokenMetadata>) {
         if let Some(ft_metadata_value) = ft_metadata {
             self.meta.set(&ft_metadata_value);
+            Event::MetadataSync {
+                name: ft_metadata_value.name,
+                symbol: ft_metadata_value.symbol,
+            }
+            .emit();
         }
         else {
             log!("Missing metadata");
@@ -244,6 +784,278 @@ impl Contract {
     }
 }
 
-fn assert_owner() {
-    assert_eq!(env::predecessor_account_id(), OWNER_ID, "No Access");
+impl Contract {
+    /// Runs `f` and updates the number of holders from the balances of the given accounts
+    /// before and after it.
+    pub(crate) fn internal_track_holders<T>(
+        &mut self,
+        account_ids: &[AccountId],
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let was_holder: Vec<bool> = account_ids.iter().map(|a| self.is_holder(a)).collect();
+        let result = f(self);
+        for (account_id, was_holder) in account_ids.iter().zip(was_holder) {
+            match (was_holder, self.is_holder(account_id)) {
+                (false, true) => self.holder_count += 1,
+                (true, false) => self.holder_count -= 1,
+                _ => {}
+            }
+        }
+        result
+    }
+
+    fn is_holder(&self, account_id: &AccountId) -> bool {
+        account_id != &env::current_account_id()
+            && self.ft.accounts.get(account_id).unwrap_or(0) > 0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{set_context, setup_contract, SEC};
+    use near_sdk::test_utils::{accounts, testing_env_with_promise_results, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    pub(crate) fn set_promise_result(promise_result: PromiseResult) {
+        set_promise_result_at(promise_result, 0);
+    }
+
+    pub(crate) fn set_promise_result_at(promise_result: PromiseResult, block_timestamp: Timestamp) {
+        testing_env_with_promise_results(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .block_timestamp(block_timestamp)
+                .build(),
+            promise_result,
+        );
+    }
+
+    pub(crate) fn set_yocto_context(predecessor: usize, block_timestamp: Timestamp) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(predecessor))
+            .block_timestamp(block_timestamp)
+            .attached_deposit(1)
+            .build());
+    }
+
+    pub(crate) fn setup_unlocked_contract(balance: Balance) -> Contract {
+        let mut contract = setup_contract(1);
+        contract.ft.internal_register_account(accounts(2).as_ref());
+        contract.internal_mint(&accounts(2).into(), balance);
+        contract.status = Status::Unlocked;
+        contract
+    }
+
+    #[test]
+    pub fn test_unlock_after_deadline() {
+        let mut contract = setup_contract(1);
+        contract.unlock_deadline = Some(1000 * SEC);
+        set_context(1, 1000 * SEC);
+        contract.unlock_after_deadline();
+        assert!(matches!(contract.status, Status::Unlocked));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unlock deadline is not reached")]
+    pub fn test_unlock_before_deadline() {
+        let mut contract = setup_contract(1);
+        contract.unlock_deadline = Some(1000 * SEC);
+        set_context(1, 1000 * SEC - 1);
+        contract.unlock_after_deadline();
+    }
+
+    #[test]
+    #[should_panic(expected = "Already unlocked")]
+    pub fn test_unlock_after_deadline_twice() {
+        let mut contract = setup_contract(1);
+        contract.unlock_deadline = Some(1000 * SEC);
+        set_context(1, 2000 * SEC);
+        contract.unlock_after_deadline();
+        contract.unlock_after_deadline();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unlock deadline is not set")]
+    pub fn test_unlock_without_deadline() {
+        let mut contract = setup_contract(1);
+        set_context(1, 2000 * SEC);
+        contract.unlock_after_deadline();
+    }
+
+    #[test]
+    pub fn test_forced_unregister_releases_underlying() {
+        let mut contract = setup_unlocked_contract(100);
+        contract.status = Status::Locked;
+        contract.total_underlying = 110;
+        set_yocto_context(2, 0);
+        contract.storage_unregister(Some(true));
+        assert_eq!(contract.ft_total_supply().0, 0);
+        assert_eq!(contract.total_underlying, 0);
+
+        set_context(1, 0);
+        contract.ft.internal_register_account(accounts(3).as_ref());
+        contract.ft_on_transfer(accounts(3), U128(50), String::new());
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 50);
+        assert_eq!(contract.get_underlying_balance(accounts(3)).0, 50);
+    }
+
+    #[test]
+    pub fn test_redeem() {
+        let mut contract = setup_unlocked_contract(100);
+        contract.status = Status::Locked;
+        set_context(0, 0);
+        contract.ft.internal_transfer(accounts(2).as_ref(), accounts(0).as_ref(), 40, None);
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(40), String::new()),
+            PromiseOrValue::Value(U128(40))
+        ));
+
+        contract.status = Status::Unlocked;
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(40), String::new()),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+        assert_eq!(contract.ft_total_supply().0, 60);
+    }
+
+    #[test]
+    pub fn test_partial_unwrap() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap(Some(U128(40)), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 60);
+        assert_eq!(contract.pending_transfers, 40);
+
+        set_promise_result(PromiseResult::Successful(vec![]));
+        assert!(contract.after_ft_transfer(accounts(2).into(), U128(40)));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 60);
+        assert_eq!(contract.pending_transfers, 0);
+        assert_eq!(contract.holder_count, 1);
+    }
+
+    #[test]
+    pub fn test_failed_unwrap_to_receiver() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap(Some(U128(40)), Some(accounts(3)));
+
+        // The refund goes back to the unwrapping account, not the receiver
+        set_promise_result(PromiseResult::Failed);
+        assert!(!contract.after_ft_transfer(accounts(2).into(), U128(40)));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert!(!contract.ft.accounts.contains_key(accounts(3).as_ref()));
+        assert_eq!(contract.ft_total_supply().0, 100);
+    }
+
+    #[test]
+    pub fn test_full_unwrap_releases_storage() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap(None, None);
+        assert!(contract.ft.accounts.contains_key(accounts(2).as_ref()));
+
+        set_promise_result(PromiseResult::Successful(vec![]));
+        contract.after_ft_transfer(accounts(2).into(), U128(100));
+        assert!(!contract.ft.accounts.contains_key(accounts(2).as_ref()));
+        assert_eq!(contract.holder_count, 0);
+    }
+
+    #[test]
+    pub fn test_concurrent_unwraps() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap(Some(U128(50)), Some(accounts(3)));
+        contract.unwrap(Some(U128(50)), None);
+
+        // The account stays open while the other unwrap is in flight
+        set_promise_result(PromiseResult::Successful(vec![]));
+        contract.after_ft_transfer(accounts(2).into(), U128(50));
+        assert!(contract.ft.accounts.contains_key(accounts(2).as_ref()));
+        assert_eq!(contract.holder_count, 0);
+
+        set_promise_result(PromiseResult::Failed);
+        contract.after_ft_transfer(accounts(2).into(), U128(50));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 50);
+        assert_eq!(contract.holder_count, 1);
+
+        set_yocto_context(2, 0);
+        contract.unwrap(None, None);
+        set_promise_result(PromiseResult::Successful(vec![]));
+        contract.after_ft_transfer(accounts(2).into(), U128(50));
+        assert!(!contract.ft.accounts.contains_key(accounts(2).as_ref()));
+        assert_eq!(contract.holder_count, 0);
+        assert!(!contract.pending_unwraps.contains_key(accounts(2).as_ref()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't unregister the account while its unwraps are in flight")]
+    pub fn test_unregister_with_unwrap_in_flight() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap(None, Some(accounts(3)));
+        contract.storage_unregister(None);
+    }
+
+    #[test]
+    pub fn test_after_ft_transfer_call_refund() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap_call(accounts(3), Some(U128(80)), String::new());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 20);
+
+        set_promise_result(PromiseResult::Successful(b"\"30\"".to_vec()));
+        assert_eq!(contract.after_ft_transfer_call(accounts(2).into(), U128(80)).0, 30);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 70);
+        assert_eq!(contract.ft_total_supply().0, 70);
+    }
+
+    #[test]
+    pub fn test_after_ft_transfer_call_failed() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 0);
+        contract.unwrap_call(accounts(3), None, String::new());
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+
+        set_promise_result(PromiseResult::Failed);
+        assert_eq!(contract.after_ft_transfer_call(accounts(2).into(), U128(100)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.pending_transfers, 0);
+    }
+
+    #[test]
+    pub fn test_deposit_for_receiver() {
+        let mut contract = setup_contract(1);
+        set_context(1, 0);
+        contract.ft.internal_register_account(accounts(2).as_ref());
+        contract.ft.internal_register_account(accounts(3).as_ref());
+        let msg = format!(r#"{{"receiver_id": "{}"}}"#, accounts(3));
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), msg),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 100);
+
+        let msg = format!(r#"{{"receiver_id": "{}"}}"#, accounts(4));
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), msg),
+            PromiseOrValue::Value(U128(100))
+        ));
+        let msg = format!(r#"{{"receiver_id": "{}"}}"#, accounts(0));
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), msg),
+            PromiseOrValue::Value(U128(100))
+        ));
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+        let msg = r#"{"receiver_id": "Invalid"}"#.to_string();
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), msg),
+            PromiseOrValue::Value(U128(100))
+        ));
+        assert_eq!(contract.ft_total_supply().0, 100);
+    }
 }
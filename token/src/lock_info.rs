@@ -0,0 +1,156 @@
+use crate::*;
+
+/// Version of the `LockInfo` layout. Bumped whenever fields are changed or removed.
+const LOCK_INFO_VERSION: u32 = 1;
+
+/// Summary of the lock for frontends.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockInfo {
+    pub version: u32,
+    pub token_id: TokenId,
+    pub locked_token_account_id: TokenAccountId,
+    pub factory_account_id: AccountId,
+    pub total_supply: U128,
+    /// Underlying tokens backing the total supply
+    pub total_underlying: U128,
+    pub holder_count: u64,
+    pub status: Status,
+    /// Earliest time the token unlocks while `Unlocking`, if enough confirmations arrive
+    pub unlock_eta: Option<U64>,
+    pub asset_id: AssetId,
+    pub unlock_direction: UnlockDirection,
+    /// Target price of a whole underlying token
+    pub target_price: String,
+    pub minimum_unlock_price: Price,
+    pub last_oracle_report: Option<OracleReport>,
+    pub price_oracle_account_ids: Vec<AccountId>,
+    pub oracle_quorum: u32,
+    pub oracle_feed: OracleFeed,
+    pub unlocking_duration_sec: DurationSec,
+    pub min_confirmations: u32,
+    pub max_confirmation_gap_sec: DurationSec,
+    pub max_price_age_sec: DurationSec,
+    pub unlock_deadline: Option<U64>,
+    pub guardian_approval_period_sec: DurationSec,
+    pub emergency_unlock_delay_sec: DurationSec,
+    pub emergency_unlock_timestamp: Option<U64>,
+    pub pause_state: PauseState,
+    pub owner_id: AccountId,
+    pub proposed_owner_id: Option<AccountId>,
+    pub guardian_ids: Vec<AccountId>,
+    pub guardian_threshold: u32,
+    /// Guardian approvals which haven't expired yet
+    pub guardian_approvals: Vec<GuardianApproval>,
+    pub early_unwrap_penalty_bps: u32,
+    pub penalty_beneficiary_id: Option<AccountId>,
+    /// Pays the storage of new depositors
+    pub storage_pool: U128,
+    pub min_sponsored_deposit: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_info(&self) -> LockInfo {
+        let unlock_eta = match self.status {
+            Status::Unlocking {
+                initiated_timestamp,
+                ..
+            } => Some((initiated_timestamp + self.unlocking_duration()).into()),
+            _ => None,
+        };
+        LockInfo {
+            version: LOCK_INFO_VERSION,
+            token_id: self.token_id.clone(),
+            locked_token_account_id: self.locked_token_account_id.clone(),
+            factory_account_id: self.factory_account_id.clone(),
+            total_supply: U128(self.ft.total_supply),
+            total_underlying: U128(self.total_underlying),
+            holder_count: self.holder_count,
+            status: self.status,
+            unlock_eta,
+            asset_id: self.asset_id.clone(),
+            unlock_direction: self.unlock_direction,
+            target_price: self
+                .minimum_unlock_price
+                .to_decimal_string(self.ft_metadata().decimals),
+            minimum_unlock_price: self.minimum_unlock_price,
+            last_oracle_report: self
+                .oracle_reports
+                .iter()
+                .max_by_key(|report| report.timestamp)
+                .cloned(),
+            price_oracle_account_ids: self.price_oracle_account_ids.clone(),
+            oracle_quorum: self.oracle_quorum,
+            oracle_feed: self.oracle_feed.clone(),
+            unlocking_duration_sec: self.unlocking_duration_sec,
+            min_confirmations: self.min_confirmations,
+            max_confirmation_gap_sec: self.max_confirmation_gap_sec,
+            max_price_age_sec: self.max_price_age_sec,
+            unlock_deadline: self.unlock_deadline.map(|d| d.into()),
+            guardian_approval_period_sec: self.guardian_approval_period_sec,
+            emergency_unlock_delay_sec: self.emergency_unlock_delay_sec,
+            emergency_unlock_timestamp: self.get_emergency_unlock_timestamp(),
+            pause_state: self.pause_state,
+            owner_id: self.owner_id.clone(),
+            proposed_owner_id: self.proposed_owner_id.clone(),
+            guardian_ids: self.guardian_ids.clone(),
+            guardian_threshold: self.guardian_threshold,
+            guardian_approvals: self.get_guardian_approvals(),
+            early_unwrap_penalty_bps: self.early_unwrap_penalty_bps,
+            penalty_beneficiary_id: self.penalty_beneficiary_id.clone(),
+            storage_pool: U128(self.storage_pool),
+            min_sponsored_deposit: U128(self.min_sponsored_deposit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{report, set_context, setup_contract, SEC};
+    use crate::tests::set_yocto_context;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    #[test]
+    pub fn test_get_info() {
+        let mut contract = setup_contract(1);
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(10u128.pow(24))
+            .build());
+        contract.storage_deposit(None, None);
+        contract.storage_deposit(Some(accounts(3)), None);
+        set_context(1, 0);
+        contract.ft_on_transfer(accounts(2), U128(100), String::new());
+
+        set_context(2, 1000 * SEC);
+        contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new());
+        let info = contract.get_info();
+        // Registered accounts without a balance aren't counted
+        assert_eq!(info.holder_count, 1);
+        assert_eq!(info.total_supply.0, 100);
+        assert_eq!(info.unlock_eta, Some(U64(4600 * SEC)));
+        assert_eq!(info.last_oracle_report.unwrap().timestamp, 1000 * SEC);
+        assert_eq!(info.unlocking_duration_sec, 3600);
+        assert_eq!(info.min_confirmations, 2);
+        assert_eq!(info.max_confirmation_gap_sec, 3600);
+        assert_eq!(info.max_price_age_sec, 300);
+        assert_eq!(info.guardian_approval_period_sec, 3600);
+        assert_eq!(info.emergency_unlock_delay_sec, 3600);
+        assert_eq!(info.storage_pool.0, 0);
+        assert_eq!(info.min_sponsored_deposit.0, 100);
+
+        set_yocto_context(2, 0);
+        contract.ft_transfer(accounts(3), U128(40), None);
+        assert_eq!(contract.get_info().holder_count, 2);
+        set_yocto_context(3, 0);
+        contract.ft_transfer(accounts(2), U128(40), None);
+        assert_eq!(contract.get_info().holder_count, 1);
+        set_yocto_context(3, 0);
+        contract.storage_unregister(None);
+        assert_eq!(contract.get_info().holder_count, 1);
+    }
+}
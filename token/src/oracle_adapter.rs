@@ -0,0 +1,251 @@
+use crate::*;
+use near_sdk::json_types::{I64, U64};
+use std::convert::TryFrom;
+
+/// A price report converted from the format of the oracle feed.
+pub struct FeedReport {
+    pub timestamp: Timestamp,
+    /// Age of the price according to the oracle, if the feed reports it
+    pub recency_duration_sec: Option<DurationSec>,
+    pub price: Option<Price>,
+    /// Half-width of the confidence interval in the decimals of the price
+    pub confidence: Option<Balance>,
+}
+
+/// Converts the data of an oracle feed into a report for the tracked asset.
+pub trait OracleAdapter {
+    /// Whether the token was created for this format.
+    fn accepts(feed: &OracleFeed) -> bool;
+
+    /// Returns the report for the tracked asset, or the reason to ignore the data.
+    fn into_report(self, contract: &Contract) -> Result<FeedReport, String>;
+}
+
+impl OracleAdapter for PriceData {
+    fn accepts(feed: &OracleFeed) -> bool {
+        matches!(feed, OracleFeed::NearPriceOracle)
+    }
+
+    fn into_report(self, contract: &Contract) -> Result<FeedReport, String> {
+        let price = self
+            .prices
+            .into_iter()
+            .find(|asset_price| asset_price.asset_id == contract.asset_id)
+            .ok_or_else(|| format!("Missing asset {}", contract.asset_id))?
+            .price;
+        Ok(FeedReport {
+            timestamp: self.timestamp,
+            recency_duration_sec: Some(self.recency_duration_sec),
+            price,
+            confidence: None,
+        })
+    }
+}
+
+/// Pyth-style price of a whole token: `price * 10^expo` plus or minus `conf * 10^expo`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PythPrice {
+    pub price: I64,
+    pub conf: U64,
+    pub expo: i32,
+    /// Unix time in seconds
+    pub publish_time: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PythPriceFeed {
+    pub id: String,
+    pub price: PythPrice,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PythPriceUpdate {
+    pub price_feeds: Vec<PythPriceFeed>,
+}
+
+impl OracleAdapter for PythPriceUpdate {
+    fn accepts(feed: &OracleFeed) -> bool {
+        matches!(feed, OracleFeed::Pyth { .. })
+    }
+
+    fn into_report(self, contract: &Contract) -> Result<FeedReport, String> {
+        let (price_id, max_confidence_bps) = match &contract.oracle_feed {
+            OracleFeed::Pyth {
+                price_id,
+                max_confidence_bps,
+            } => (price_id, *max_confidence_bps),
+            OracleFeed::NearPriceOracle => unreachable!(),
+        };
+        let feed = self
+            .price_feeds
+            .into_iter()
+            .find(|feed| &feed.id == price_id)
+            .ok_or_else(|| format!("Missing price feed {}", price_id))?;
+        let PythPrice {
+            price,
+            conf,
+            expo,
+            publish_time,
+        } = feed.price;
+        let timestamp = u64::try_from(publish_time)
+            .ok()
+            .and_then(|sec| sec.checked_mul(10u64.pow(9)))
+            .ok_or_else(|| format!("Invalid publish time {}", publish_time))?;
+        if price.0 <= 0 {
+            return Err(format!("Price {} is not positive", price.0));
+        }
+        let (price, conf) = (price.0 as u128, u128::from(conf.0));
+        if conf * MAX_BPS as u128 > price * max_confidence_bps as u128 {
+            return Err(format!(
+                "Confidence interval {} of the price {} exceeds {} basis points",
+                conf, price, max_confidence_bps
+            ));
+        }
+        // The price is per whole token, while `Price` is per base unit of the asset
+        let decimals = i32::from(contract.ft_metadata().decimals) - expo;
+        let (scale, decimals) = if decimals < 0 {
+            (10u128.checked_pow(decimals.unsigned_abs()), 0)
+        } else {
+            (Some(1), decimals)
+        };
+        let out_of_range = || format!("Exponent {} is out of range", expo);
+        let decimals = u8::try_from(decimals).map_err(|_| out_of_range())?;
+        let scale = scale.ok_or_else(out_of_range)?;
+        Ok(FeedReport {
+            timestamp,
+            recency_duration_sec: None,
+            price: Some(Price {
+                multiplier: price.checked_mul(scale).ok_or_else(out_of_range)?,
+                decimals,
+            }),
+            confidence: Some(conf * scale),
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Receives Pyth-style prices relayed by one of the price oracle accounts.
+    pub fn on_pyth_price_update(&mut self, data: PythPriceUpdate) -> OracleOutcome {
+        self.internal_on_feed(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{report, set_context, setup_contract, SEC};
+    use near_sdk::test_utils::accounts;
+
+    const PRICE_ID: &str = "c415de8d2eba7db216527dff4b60e8f3a5311c740dadb233e13e12547e226750";
+
+    fn setup_pyth_contract() -> Contract {
+        let mut contract = setup_contract(1);
+        contract.oracle_feed = OracleFeed::Pyth {
+            price_id: PRICE_ID.to_string(),
+            max_confidence_bps: 100,
+        };
+        // $10 per NEAR
+        contract.minimum_unlock_price = Price {
+            multiplier: 10 * 10u128.pow(8),
+            decimals: 32,
+        };
+        contract
+    }
+
+    /// Price and confidence in cents
+    fn pyth_update(publish_time: i64, price: i64, conf: u64) -> PythPriceUpdate {
+        PythPriceUpdate {
+            price_feeds: vec![PythPriceFeed {
+                id: PRICE_ID.to_string(),
+                price: PythPrice {
+                    price: I64(price * 10i64.pow(6)),
+                    conf: U64(conf * 10u64.pow(6)),
+                    expo: -8,
+                    publish_time,
+                },
+            }],
+        }
+    }
+
+    #[test]
+    pub fn test_pyth_price_conversion() {
+        let mut contract = setup_pyth_contract();
+
+        set_context(2, 1000 * SEC);
+        contract.on_pyth_price_update(pyth_update(1000, 1100, 5));
+        let report = &contract.oracle_reports[0];
+        assert_eq!(report.timestamp, 1000 * SEC);
+        // $11 per NEAR is 11 * 10^-24 per yoctoNEAR
+        assert_eq!(
+            report.price,
+            Some(Price {
+                multiplier: 11 * 10u128.pow(8),
+                decimals: 32
+            })
+        );
+        assert_eq!(report.confidence, Some(U128(5 * 10u128.pow(6))));
+    }
+
+    #[test]
+    pub fn test_pyth_confidence() {
+        let mut contract = setup_pyth_contract();
+
+        set_context(2, 1000 * SEC);
+        assert!(matches!(
+            contract.on_pyth_price_update(pyth_update(1000, 1010, 20)),
+            OracleOutcome::Ignored { .. }
+        ));
+        // The lower end of the confidence interval decides the unlock
+        assert_eq!(
+            contract.on_pyth_price_update(pyth_update(1000, 1005, 10)),
+            OracleOutcome::NoOp
+        );
+        assert_eq!(
+            contract.on_pyth_price_update(pyth_update(1010, 1015, 10)),
+            OracleOutcome::StartedUnlocking
+        );
+        assert_eq!(
+            contract.on_pyth_price_update(pyth_update(1020, 1008, 10)),
+            OracleOutcome::Relocked
+        );
+        assert_eq!(contract.get_price_history_length(), 3);
+    }
+
+    #[test]
+    pub fn test_pyth_rejected_reports() {
+        let mut contract = setup_pyth_contract();
+
+        set_context(2, 1000 * SEC);
+        let mut update = pyth_update(1000, 1100, 5);
+        update.price_feeds[0].id =
+            "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d".to_string();
+        assert_eq!(
+            contract.on_pyth_price_update(update),
+            OracleOutcome::Ignored {
+                reason: format!("Missing price feed {}", PRICE_ID)
+            }
+        );
+        assert!(matches!(
+            contract.on_pyth_price_update(pyth_update(1000, -1100, 5)),
+            OracleOutcome::Ignored { .. }
+        ));
+        assert!(matches!(
+            contract.on_pyth_price_update(pyth_update(600, 1100, 5)),
+            OracleOutcome::Ignored { .. }
+        ));
+        assert!(contract.oracle_reports.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The token expects Pyth")]
+    pub fn test_wrong_feed_format() {
+        let mut contract = setup_pyth_contract();
+
+        set_context(2, 1000 * SEC);
+        contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new());
+    }
+}
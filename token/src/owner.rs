@@ -0,0 +1,123 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Proposes a new owner. The ownership is transferred once the new owner accepts it.
+    #[payable]
+    pub fn propose_owner(&mut self, owner_id: Option<ValidAccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.proposed_owner_id = owner_id.map(|a| a.into());
+        Event::OwnerProposed {
+            owner_id: self.owner_id.clone(),
+            proposed_owner_id: self.proposed_owner_id.clone(),
+        }
+        .emit();
+    }
+
+    #[payable]
+    pub fn accept_owner(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert_eq!(
+            Some(&account_id),
+            self.proposed_owner_id.as_ref(),
+            "Not the proposed owner"
+        );
+        Event::OwnerChange {
+            old_owner_id: self.owner_id.clone(),
+            new_owner_id: account_id.clone(),
+        }
+        .emit();
+        self.owner_id = account_id;
+        self.proposed_owner_id = None;
+    }
+
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_proposed_owner_id(&self) -> Option<AccountId> {
+        self.proposed_owner_id.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "No Access");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::price_receiver::tests::{setup_contract, SEC};
+    use crate::tests::set_yocto_context;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_transfer_ownership() {
+        let mut contract = setup_contract(1);
+
+        set_yocto_context(5, 1000 * SEC);
+        contract.propose_owner(Some(accounts(3)));
+        assert_eq!(contract.get_owner_id(), accounts(5).to_string());
+        assert_eq!(
+            contract.get_proposed_owner_id(),
+            Some(accounts(3).to_string())
+        );
+
+        set_yocto_context(3, 1000 * SEC);
+        contract.accept_owner();
+        assert_eq!(contract.get_owner_id(), accounts(3).to_string());
+        assert_eq!(contract.get_proposed_owner_id(), None);
+        contract.unpause(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_old_owner_loses_access() {
+        let mut contract = setup_contract(1);
+
+        set_yocto_context(5, 1000 * SEC);
+        contract.propose_owner(Some(accounts(3)));
+        set_yocto_context(3, 1000 * SEC);
+        contract.accept_owner();
+
+        set_yocto_context(5, 1000 * SEC);
+        contract.propose_owner(Some(accounts(5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Not the proposed owner")]
+    pub fn test_accept_owner_by_other_account() {
+        let mut contract = setup_contract(1);
+
+        set_yocto_context(5, 1000 * SEC);
+        contract.propose_owner(Some(accounts(3)));
+        set_yocto_context(4, 1000 * SEC);
+        contract.accept_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "Not the proposed owner")]
+    pub fn test_cancel_proposed_owner() {
+        let mut contract = setup_contract(1);
+
+        set_yocto_context(5, 1000 * SEC);
+        contract.propose_owner(Some(accounts(3)));
+        contract.propose_owner(None);
+        assert_eq!(contract.get_proposed_owner_id(), None);
+
+        set_yocto_context(3, 1000 * SEC);
+        contract.accept_owner();
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_propose_owner_by_other_account() {
+        let mut contract = setup_contract(1);
+
+        set_yocto_context(3, 1000 * SEC);
+        contract.propose_owner(Some(accounts(3)));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseState {
+    /// `ft_on_transfer` deposits of the underlying token
+    pub deposits: bool,
+    /// `ft_transfer` and `ft_transfer_call` of the locked token
+    pub transfers: bool,
+    /// `unwrap`, `unwrap_call`, `early_unwrap`, redemptions and automatic deliveries
+    pub unwraps: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PausableAction {
+    Deposits,
+    Transfers,
+    Unwraps,
+}
+
+impl PauseState {
+    pub fn is_paused(&self, action: PausableAction) -> bool {
+        match action {
+            PausableAction::Deposits => self.deposits,
+            PausableAction::Transfers => self.transfers,
+            PausableAction::Unwraps => self.unwraps,
+        }
+    }
+
+    fn set(&mut self, action: PausableAction, paused: bool) {
+        match action {
+            PausableAction::Deposits => self.deposits = paused,
+            PausableAction::Transfers => self.transfers = paused,
+            PausableAction::Unwraps => self.unwraps = paused,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses the given actions. Can be called by the owner or any guardian.
+    #[payable]
+    pub fn pause(&mut self, actions: Vec<PausableAction>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert!(
+            account_id == self.owner_id || self.guardian_ids.contains(&account_id),
+            "No Access"
+        );
+        self.internal_set_paused(account_id, actions, true);
+    }
+
+    /// Resumes the given actions. Can only be called by the owner.
+    #[payable]
+    pub fn unpause(&mut self, actions: Vec<PausableAction>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_set_paused(env::predecessor_account_id(), actions, false);
+    }
+
+    pub fn get_pause_state(&self) -> PauseState {
+        self.pause_state
+    }
+}
+
+impl Contract {
+    fn internal_set_paused(
+        &mut self,
+        account_id: AccountId,
+        actions: Vec<PausableAction>,
+        paused: bool,
+    ) {
+        for action in actions {
+            self.pause_state.set(action, paused);
+        }
+        Event::PauseChange {
+            account_id,
+            pause_state: self.pause_state,
+        }
+        .emit();
+    }
+
+    pub(crate) fn assert_not_paused(&self, action: PausableAction) {
+        match action {
+            PausableAction::Deposits => assert!(!self.pause_state.deposits, "Deposits are paused"),
+            PausableAction::Transfers => {
+                assert!(!self.pause_state.transfers, "Transfers are paused")
+            }
+            PausableAction::Unwraps => assert!(!self.pause_state.unwraps, "Unwraps are paused"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::set_context;
+    use crate::tests::{set_yocto_context, setup_unlocked_contract};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_pause() {
+        let mut contract = setup_unlocked_contract(100);
+        contract.guardian_ids = vec![accounts(3).into()];
+        set_yocto_context(3, 0);
+        contract.pause(vec![PausableAction::Transfers, PausableAction::Unwraps]);
+        assert!(contract.get_pause_state().transfers);
+        assert!(contract.get_pause_state().unwraps);
+        assert!(!contract.get_pause_state().deposits);
+
+        // Redemptions are returned while unwraps are paused
+        set_context(0, 0);
+        contract
+            .ft
+            .internal_transfer(accounts(2).as_ref(), accounts(0).as_ref(), 40, None);
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(40), String::new()),
+            PromiseOrValue::Value(U128(40))
+        ));
+
+        set_yocto_context(5, 0);
+        contract.unpause(vec![PausableAction::Unwraps]);
+        assert!(!contract.get_pause_state().unwraps);
+        assert!(contract.get_pause_state().transfers);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfers are paused")]
+    pub fn test_transfer_paused() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(5, 0);
+        contract.pause(vec![PausableAction::Transfers]);
+        set_yocto_context(2, 0);
+        contract.ft_transfer(accounts(0), U128(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_unpause_by_guardian() {
+        let mut contract = setup_unlocked_contract(100);
+        contract.guardian_ids = vec![accounts(3).into()];
+        set_yocto_context(3, 0);
+        contract.pause(vec![PausableAction::Deposits]);
+        contract.unpause(vec![PausableAction::Deposits]);
+    }
+}
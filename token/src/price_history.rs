@@ -0,0 +1,75 @@
+use crate::*;
+
+/// Number of accepted oracle reports kept in the history. Older reports are overwritten.
+const PRICE_HISTORY_CAPACITY: u64 = 100;
+
+/// An accepted oracle report and the status of the token after processing it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceRecord {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price: Option<Price>,
+    pub status: Status,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Accepted oracle reports from the oldest to the newest one kept in the history.
+    pub fn get_price_history(&self, from_index: u64, limit: u64) -> Vec<PriceRecord> {
+        let len = self.price_history.len();
+        let oldest = if self.price_history_count > len {
+            self.price_history_count % len
+        } else {
+            0
+        };
+        (from_index..std::cmp::min(from_index.saturating_add(limit), len))
+            .filter_map(|index| self.price_history.get((oldest + index) % len))
+            .collect()
+    }
+
+    pub fn get_price_history_length(&self) -> u64 {
+        self.price_history.len()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_record_price(&mut self, record: PriceRecord) {
+        if self.price_history.len() < PRICE_HISTORY_CAPACITY {
+            self.price_history.push(&record);
+        } else {
+            self.price_history
+                .replace(self.price_history_count % PRICE_HISTORY_CAPACITY, &record);
+        }
+        self.price_history_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::setup_contract;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_price_history() {
+        let mut contract = setup_contract(1);
+        for timestamp in 0..105 {
+            contract.internal_record_price(PriceRecord {
+                oracle_id: accounts(2).into(),
+                timestamp,
+                price: None,
+                status: Status::Locked,
+            });
+        }
+        assert_eq!(contract.get_price_history_length(), 100);
+        let history = contract.get_price_history(0, 200);
+        assert_eq!(history.len(), 100);
+        assert_eq!(history[0].timestamp, 5);
+        assert_eq!(history[99].timestamp, 104);
+        let page = contract.get_price_history(98, 10);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].timestamp, 103);
+    }
+}
@@ -1,12 +1,8 @@
 use crate::*;
 use near_sdk::{Duration, Timestamp};
-use std::cmp::Ordering;
 
-pub type AssetId = String;
-pub type DurationSec = u32;
-
-const MAX_U128_DECIMALS: u8 = 38;
-const UNLOCKING_DURATION: Duration = 24 * 60 * 60 * 10u64.pow(9);
+/// Reports can be ahead of the block time by this much to allow for clock differences.
+const MAX_CLOCK_SKEW_SEC: DurationSec = 60;
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -25,173 +21,307 @@ pub struct PriceData {
     pub prices: Vec<AssetOptionalPrice>,
 }
 
-pub trait OraclePriceReceiver {
-    fn oracle_on_call(&mut self, sender_id: AccountId, data: PriceData, msg: String);
-}
-
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+/// The latest report of a price oracle for the tracked asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
-pub struct Price {
-    #[serde(with = "u128_dec_format")]
-    multiplier: Balance,
-    decimals: u8,
+pub struct OracleReport {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price: Option<Price>,
+    /// Half-width of the confidence interval in the decimals of the price, if the feed reports it
+    pub confidence: Option<U128>,
 }
 
-impl PartialEq<Self> for Price {
-    fn eq(&self, other: &Self) -> bool {
-        self.partial_cmp(other) == Some(Ordering::Equal)
+impl OracleReport {
+    /// The end of the confidence interval which is the furthest from the target price.
+    pub fn conservative_price(&self, unlock_direction: UnlockDirection) -> Option<Price> {
+        let confidence = self.confidence.map(|c| c.0).unwrap_or(0);
+        self.price.map(|price| Price {
+            multiplier: match unlock_direction {
+                UnlockDirection::Above => price.multiplier.saturating_sub(confidence),
+                UnlockDirection::Below => price.multiplier.saturating_add(confidence),
+            },
+            decimals: price.decimals,
+        })
     }
 }
 
-impl PartialOrd for Price {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.decimals < other.decimals {
-            return other.partial_cmp(self).map(|o| o.reverse());
-        }
-
-        let decimals_diff = self.decimals - other.decimals;
-
-        if decimals_diff > MAX_U128_DECIMALS {
-            return Some(Ordering::Less);
-        }
+/// Result of processing an oracle report.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OracleOutcome {
+    /// The report was accepted, but the status didn't change.
+    NoOp,
+    StartedUnlocking,
+    Relocked,
+    Unlocked,
+    /// The report was rejected, e.g. stale or out of order.
+    Ignored {
+        reason: String,
+    },
+}
 
-        if let Some(om) = other
-            .multiplier
-            .checked_mul(10u128.pow(decimals_diff as u32))
-        {
-            Some(self.multiplier.cmp(&om))
-        } else {
-            Some(Ordering::Less)
-        }
-    }
+pub trait OraclePriceReceiver {
+    fn oracle_on_call(
+        &mut self,
+        sender_id: AccountId,
+        data: PriceData,
+        msg: String,
+    ) -> OracleOutcome;
 }
 
-pub mod u64_dec_format {
-    use near_sdk::serde::de;
-    use near_sdk::serde::{Deserialize, Deserializer, Serializer};
+/// Returns the lower median of the given non-empty list of prices.
+pub fn median_price(mut prices: Vec<Price>) -> Price {
+    prices.sort();
+    prices[(prices.len() - 1) / 2]
+}
 
-    pub fn serialize<S>(num: &u64, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&num.to_string())
-    }
+pub fn to_nano(sec: DurationSec) -> Duration {
+    Duration::from(sec) * 10u64.pow(9)
+}
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        String::deserialize(deserializer)?
-            .parse()
-            .map_err(de::Error::custom)
+#[near_bindgen]
+impl OraclePriceReceiver for Contract {
+    #[allow(unused_variables)]
+    fn oracle_on_call(
+        &mut self,
+        sender_id: AccountId,
+        data: PriceData,
+        msg: String,
+    ) -> OracleOutcome {
+        self.internal_on_feed(data)
     }
 }
 
-pub mod u128_dec_format {
-    use near_sdk::serde::de;
-    use near_sdk::serde::{Deserialize, Deserializer, Serializer};
+impl Contract {
+    pub fn unlocking_duration(&self) -> Duration {
+        to_nano(self.unlocking_duration_sec)
+    }
 
-    pub fn serialize<S>(num: &u128, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&num.to_string())
+    /// Processes the data of an oracle feed sent by one of the price oracles.
+    pub(crate) fn internal_on_feed<A: OracleAdapter>(&mut self, data: A) -> OracleOutcome {
+        let oracle_id = env::predecessor_account_id();
+        assert!(
+            self.price_oracle_account_ids.contains(&oracle_id),
+            "Unknown price oracle {}",
+            oracle_id
+        );
+        assert!(
+            A::accepts(&self.oracle_feed),
+            "The token expects {:?} reports",
+            self.oracle_feed
+        );
+        let outcome = self.internal_on_report(oracle_id, data);
+        if let OracleOutcome::Ignored { reason } = &outcome {
+            log!("Ignored report: {}", reason);
+        }
+        outcome
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        String::deserialize(deserializer)?
-            .parse()
-            .map_err(de::Error::custom)
+    fn internal_on_report(
+        &mut self,
+        oracle_id: AccountId,
+        data: impl OracleAdapter,
+    ) -> OracleOutcome {
+        if matches!(self.status, Status::Unlocked) {
+            return OracleOutcome::NoOp;
+        }
+        let report = match data.into_report(self) {
+            Ok(report) => report,
+            Err(reason) => return OracleOutcome::Ignored { reason },
+        };
+        if let Err(reason) = self.check_fresh_report(&oracle_id, &report) {
+            return OracleOutcome::Ignored { reason };
+        }
+        self.internal_set_oracle_report(OracleReport {
+            oracle_id: oracle_id.clone(),
+            timestamp: report.timestamp,
+            price: report.price,
+            confidence: report.confidence.map(U128),
+        });
+        let outcome = self.internal_evaluate_prices(report.timestamp);
+        self.internal_record_price(PriceRecord {
+            oracle_id,
+            timestamp: report.timestamp,
+            price: report.price,
+            status: self.status,
+        });
+        outcome
     }
-}
 
-#[near_bindgen]
-impl OraclePriceReceiver for Contract {
-    #[allow(unused_variables)]
-    fn oracle_on_call(&mut self, sender_id: AccountId, data: PriceData, msg: String) {
-        assert_eq!(
-            &env::predecessor_account_id(),
-            &self.price_oracle_account_id
+    /// Updates the status based on the median of the fresh oracle reports.
+    fn internal_evaluate_prices(&mut self, report_timestamp: Timestamp) -> OracleOutcome {
+        let prices = self.get_fresh_prices();
+        if (prices.len() as u32) < self.oracle_quorum {
+            log!(
+                "Waiting for quorum, {}/{} fresh reports",
+                prices.len(),
+                self.oracle_quorum
+            );
+            return OracleOutcome::NoOp;
+        }
+        let price = median_price(prices);
+        log!(
+            "maybe_unlock if median {}/{} {} {}/{}",
+            price.multiplier,
+            price.decimals,
+            self.unlock_direction.as_operator(),
+            self.minimum_unlock_price.multiplier,
+            self.minimum_unlock_price.decimals
         );
-        for AssetOptionalPrice { asset_id, price } in data.prices {
-            if asset_id == self.asset_id {
-                if let Some(price) = price {
-                    log!(
-                        "maybe_unlock if {}/{} >= {}/{}",
-                        price.multiplier,
-                        price.decimals,
-                        self.minimum_unlock_price.multiplier,
-                        self.minimum_unlock_price.decimals
-                    );
-                    if price >= self.minimum_unlock_price {
-                        self.maybe_unlock();
-                        return;
-                    }
-                }
-                self.maybe_lock();
-                return;
+        if self
+            .unlock_direction
+            .is_reached(&price, &self.minimum_unlock_price)
+        {
+            self.maybe_unlock(report_timestamp)
+        } else {
+            self.maybe_lock()
+        }
+    }
+
+    /// Rejects replayed, reordered or delayed oracle reports.
+    fn check_fresh_report(&self, oracle_id: &AccountId, data: &FeedReport) -> Result<(), String> {
+        let timestamp = env::block_timestamp();
+        if data.timestamp > timestamp.saturating_add(to_nano(MAX_CLOCK_SKEW_SEC)) {
+            return Err(format!(
+                "Report from {} is in the future, current time is {}",
+                data.timestamp, timestamp
+            ));
+        }
+        if let Some(last_report) = self
+            .oracle_reports
+            .iter()
+            .find(|report| &report.oracle_id == oracle_id)
+        {
+            if data.timestamp <= last_report.timestamp {
+                return Err(format!(
+                    "Report from {} is not newer than the last accepted report from {}",
+                    data.timestamp, last_report.timestamp
+                ));
             }
         }
+        if let Some(recency_duration_sec) = data.recency_duration_sec {
+            if recency_duration_sec > self.max_price_age_sec {
+                return Err(format!(
+                    "Report recency of {} seconds exceeds the maximum price age of {} seconds",
+                    recency_duration_sec, self.max_price_age_sec
+                ));
+            }
+        }
+        if data
+            .timestamp
+            .saturating_add(to_nano(self.max_price_age_sec))
+            < timestamp
+        {
+            return Err(format!(
+                "Report from {} is stale, current time is {}",
+                data.timestamp, timestamp
+            ));
+        }
+        Ok(())
     }
-}
 
-impl Contract {
-    pub fn maybe_unlock(&mut self) {
+    fn internal_set_oracle_report(&mut self, report: OracleReport) {
+        self.oracle_reports
+            .retain(|last_report| last_report.oracle_id != report.oracle_id);
+        self.oracle_reports.push(report);
+    }
+
+    /// Prices of the latest reports which are not older than the maximum price age, taking the
+    /// end of the confidence interval which is the furthest from the target price.
+    fn get_fresh_prices(&self) -> Vec<Price> {
+        let timestamp = env::block_timestamp();
+        let max_price_age = to_nano(self.max_price_age_sec);
+        self.oracle_reports
+            .iter()
+            .filter(|report| report.timestamp.saturating_add(max_price_age) >= timestamp)
+            .filter_map(|report| report.conservative_price(self.unlock_direction))
+            .collect()
+    }
+
+    pub fn maybe_unlock(&mut self, report_timestamp: Timestamp) -> OracleOutcome {
         match self.status {
-            Status::Locked => {
-                let initiated_timestamp = env::block_timestamp();
-                self.status = Status::Unlocking {
-                    initiated_timestamp,
-                };
-                log!(
-                    "Started unlocking at {}, unlocks at {}",
-                    initiated_timestamp,
-                    initiated_timestamp + UNLOCKING_DURATION
-                );
-            }
+            Status::Locked => self.start_unlocking(report_timestamp),
             Status::Unlocking {
                 initiated_timestamp,
+                last_report_timestamp,
+                confirmations,
             } => {
+                if report_timestamp <= last_report_timestamp {
+                    log!(
+                        "Report from {} is already counted, last confirmation at {}",
+                        report_timestamp,
+                        last_report_timestamp
+                    );
+                    return OracleOutcome::NoOp;
+                }
+                let gap = report_timestamp - last_report_timestamp;
+                if gap > to_nano(self.max_confirmation_gap_sec) {
+                    log!("No confirmations for {} ns, restarting unlocking", gap);
+                    return self.start_unlocking(report_timestamp);
+                }
+
+                let confirmations = confirmations + 1;
                 let timestamp = env::block_timestamp();
-                if initiated_timestamp + UNLOCKING_DURATION > timestamp {
+                let unlocks_at = initiated_timestamp + self.unlocking_duration();
+                if unlocks_at > timestamp || confirmations < self.min_confirmations {
                     log!(
-                        "Still unlocking, unlocks at {}, but current time is {}",
-                        initiated_timestamp + UNLOCKING_DURATION,
+                        "Still unlocking with {}/{} confirmations, unlocks at {}, but current time is {}",
+                        confirmations,
+                        self.min_confirmations,
+                        unlocks_at,
                         timestamp
                     );
+                    self.status = Status::Unlocking {
+                        initiated_timestamp,
+                        last_report_timestamp: report_timestamp,
+                        confirmations,
+                    };
+                    OracleOutcome::NoOp
                 } else {
-                    log!("Unlocked!");
-                    self.status = Status::Unlocked;
+                    self.internal_set_status(Status::Unlocked);
+                    OracleOutcome::Unlocked
                 }
             }
-            Status::Unlocked => {
-                env::panic(b"Already unlocked");
-            }
+            Status::Unlocked => OracleOutcome::NoOp,
         }
     }
 
-    pub fn maybe_lock(&mut self) {
+    fn start_unlocking(&mut self, report_timestamp: Timestamp) -> OracleOutcome {
+        let initiated_timestamp = env::block_timestamp();
+        self.internal_set_status(Status::Unlocking {
+            initiated_timestamp,
+            last_report_timestamp: report_timestamp,
+            confirmations: 0,
+        });
+        log!(
+            "Started unlocking at {}, unlocks at {} after {} confirmations",
+            initiated_timestamp,
+            initiated_timestamp + self.unlocking_duration(),
+            self.min_confirmations
+        );
+        OracleOutcome::StartedUnlocking
+    }
+
+    pub fn maybe_lock(&mut self) -> OracleOutcome {
         match self.status {
-            Status::Locked => {
-                env::panic(b"Still locked");
-            }
             Status::Unlocking { .. } => {
-                self.status = Status::Locked;
-                log!("Locked again");
-            }
-            Status::Unlocked => {
-                env::panic(b"Already unlocked");
+                self.internal_set_status(Status::Locked);
+                OracleOutcome::Relocked
             }
+            Status::Locked | Status::Unlocked => OracleOutcome::NoOp,
         }
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, MockedBlockchain};
+
+    pub(crate) const SEC: Timestamp = 10u64.pow(9);
 
     fn p(multiplier: u128, decimals: u8) -> Price {
         Price {
@@ -201,24 +331,282 @@ mod tests {
     }
 
     #[test]
-    pub fn test_price_cmp() {
-        assert!(p(10, 0) < p(11, 0));
-        assert!(p(11, 0) > p(10, 0));
-        assert!(p(11, 0) == p(11, 0));
+    pub fn test_median_price() {
+        assert!(median_price(vec![p(10, 0)]) == p(10, 0));
+        assert!(median_price(vec![p(12, 0), p(10, 0), p(110, 1)]) == p(11, 0));
+        assert!(median_price(vec![p(13, 0), p(10, 0), p(12, 0), p(11, 0)]) == p(11, 0));
+    }
 
-        assert!(p(10, 10) < p(11, 10));
-        assert!(p(11, 10) > p(10, 10));
-        assert!(p(11, 10) == p(11, 10));
+    pub(crate) fn set_context(predecessor: usize, block_timestamp: Timestamp) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(predecessor))
+            .block_timestamp(block_timestamp)
+            .build());
+    }
 
-        assert!(p(100, 10) == p(10, 9));
-        assert!(p(10, 9) == p(100, 10));
+    pub(crate) fn report(timestamp: Timestamp, price: u128) -> PriceData {
+        PriceData {
+            timestamp,
+            recency_duration_sec: 90,
+            prices: vec![AssetOptionalPrice {
+                asset_id: "wrap.near".to_string(),
+                price: Some(p(price, 0)),
+            }],
+        }
+    }
 
-        assert!(p(101, 10) > p(10, 9));
-        assert!(p(10, 9) < p(101, 10));
-        assert!(p(99, 10) < p(10, 9));
-        assert!(p(10, 9) > p(99, 10));
+    pub(crate) fn setup_contract(oracle_quorum: u32) -> Contract {
+        set_context(0, 1000 * SEC);
+        Contract::new(TokenArgs {
+            locked_token_account_id: accounts(1).into(),
+            token_id: "near-at-10-0".to_string(),
+            meta: FungibleTokenMetadata {
+                spec: "ft-1.0.0".to_string(),
+                name: "NEAR at $10".to_string(),
+                symbol: "NEAR@10".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 24,
+            },
+            owner_id: accounts(5).into(),
+            guardian_ids: vec![],
+            guardian_threshold: 0,
+            guardian_approval_period_sec: 3600,
+            emergency_unlock_delay_sec: 3600,
+            price_oracle_account_ids: vec![
+                accounts(2).into(),
+                accounts(3).into(),
+                accounts(4).into(),
+            ],
+            oracle_quorum,
+            oracle_feed: OracleFeed::NearPriceOracle,
+            asset_id: "wrap.near".to_string(),
+            minimum_unlock_price: p(10, 0),
+            unlock_direction: UnlockDirection::Above,
+            unlock_deadline: None,
+            unlocking_duration_sec: 3600,
+            min_confirmations: 2,
+            max_confirmation_gap_sec: 3600,
+            max_price_age_sec: 300,
+            early_unwrap_penalty_bps: 0,
+            penalty_beneficiary_id: None,
+            min_sponsored_deposit: 100,
+        })
+    }
 
-        assert!(p(101, 40) < p(10, 0));
-        assert!(p(10, 0) > p(101, 40));
+    #[test]
+    pub fn test_oracle_outcomes() {
+        let mut contract = setup_contract(1);
+
+        set_context(2, 1000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 9), String::new()),
+            OracleOutcome::NoOp
+        );
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(999 * SEC, 11), String::new()),
+            OracleOutcome::Ignored {
+                reason: format!(
+                    "Report from {} is not newer than the last accepted report from {}",
+                    999 * SEC,
+                    1000 * SEC
+                )
+            }
+        );
+
+        set_context(2, 2000 * SEC);
+        assert!(matches!(
+            contract.oracle_on_call(
+                accounts(0).into(),
+                report(1000 * SEC + 1, 11),
+                String::new()
+            ),
+            OracleOutcome::Ignored { .. }
+        ));
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(2000 * SEC, 11), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+
+        set_context(3, 3000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(3000 * SEC, 12), String::new()),
+            OracleOutcome::NoOp
+        );
+
+        set_context(2, 6000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(6000 * SEC, 12), String::new()),
+            OracleOutcome::Unlocked
+        );
+        assert!(matches!(contract.status, Status::Unlocked));
+
+        set_context(2, 7000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(7000 * SEC, 9), String::new()),
+            OracleOutcome::NoOp
+        );
+
+        // Only accepted reports are recorded
+        let history = contract.get_price_history(0, 10);
+        assert_eq!(history.len(), 4);
+        assert!(matches!(history[1].status, Status::Unlocking { .. }));
+        assert!(matches!(history[3].status, Status::Unlocked));
+    }
+
+    #[test]
+    pub fn test_unlocking_duration() {
+        let mut contract = setup_contract(1);
+        contract.unlocking_duration_sec = 7200;
+        contract.min_confirmations = 1;
+
+        set_context(2, 1000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+        set_context(2, 4600 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(4600 * SEC, 11), String::new()),
+            OracleOutcome::NoOp
+        );
+        set_context(2, 8200 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(8200 * SEC, 11), String::new()),
+            OracleOutcome::Unlocked
+        );
+    }
+
+    #[test]
+    pub fn test_confirmation_gap_restarts_unlocking() {
+        let mut contract = setup_contract(1);
+
+        set_context(2, 1000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+        set_context(2, 2000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(2000 * SEC, 11), String::new()),
+            OracleOutcome::NoOp
+        );
+        // No confirmation for longer than the maximum gap
+        set_context(2, 5601 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(5601 * SEC, 11), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+        assert!(matches!(
+            contract.status,
+            Status::Unlocking {
+                initiated_timestamp,
+                confirmations: 0,
+                ..
+            } if initiated_timestamp == 5601 * SEC
+        ));
+    }
+
+    #[test]
+    pub fn test_min_confirmations() {
+        let mut contract = setup_contract(1);
+        contract.min_confirmations = 3;
+
+        set_context(2, 1000 * SEC);
+        contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new());
+        // The window ends at 4600, but only two reports confirmed the price by then
+        for timestamp in [4000, 4600].iter() {
+            set_context(2, timestamp * SEC);
+            assert_eq!(
+                contract.oracle_on_call(
+                    accounts(0).into(),
+                    report(timestamp * SEC, 11),
+                    String::new()
+                ),
+                OracleOutcome::NoOp
+            );
+        }
+        set_context(2, 5000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(5000 * SEC, 11), String::new()),
+            OracleOutcome::Unlocked
+        );
+    }
+
+    #[test]
+    pub fn test_rejected_reports() {
+        let mut contract = setup_contract(1);
+        set_context(2, 1000 * SEC);
+
+        let mut delayed = report(1000 * SEC, 11);
+        delayed.recency_duration_sec = 301;
+        assert!(matches!(
+            contract.oracle_on_call(accounts(0).into(), delayed, String::new()),
+            OracleOutcome::Ignored { .. }
+        ));
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(699 * SEC, 11), String::new()),
+            OracleOutcome::Ignored {
+                reason: format!(
+                    "Report from {} is stale, current time is {}",
+                    699 * SEC,
+                    1000 * SEC
+                )
+            }
+        );
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1061 * SEC, 11), String::new()),
+            OracleOutcome::Ignored {
+                reason: format!(
+                    "Report from {} is in the future, current time is {}",
+                    1061 * SEC,
+                    1000 * SEC
+                )
+            }
+        );
+        assert!(matches!(
+            contract.oracle_on_call(accounts(0).into(), report(u64::MAX, 11), String::new()),
+            OracleOutcome::Ignored { .. }
+        ));
+        assert!(contract.oracle_reports.is_empty());
+
+        // A slightly early clock is accepted and doesn't block later reports
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1060 * SEC, 11), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+        set_context(2, 1100 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1100 * SEC, 11), String::new()),
+            OracleOutcome::NoOp
+        );
+    }
+
+    #[test]
+    pub fn test_oracle_quorum() {
+        let mut contract = setup_contract(2);
+
+        set_context(2, 1000 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1000 * SEC, 11), String::new()),
+            OracleOutcome::NoOp
+        );
+        set_context(3, 1010 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1010 * SEC, 12), String::new()),
+            OracleOutcome::StartedUnlocking
+        );
+        set_context(4, 1020 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1020 * SEC, 9), String::new()),
+            OracleOutcome::NoOp
+        );
+        set_context(2, 1030 * SEC);
+        assert_eq!(
+            contract.oracle_on_call(accounts(0).into(), report(1030 * SEC, 8), String::new()),
+            OracleOutcome::Relocked
+        );
     }
 }
@@ -0,0 +1,285 @@
+use crate::*;
+
+const GAS_FOR_FT_BALANCE_OF: Gas = 10 * TGAS;
+const GAS_FOR_ON_RECONCILE: Gas = 10 * TGAS;
+const GAS_FOR_ON_SWEEP: Gas = GAS_FOR_FT_TRANSFER + GAS_FOR_AFTER_FT_TRANSFER + 10 * TGAS;
+/// Deposits in flight during a balance query settle long before the second step of the sweep.
+const SWEEP_SETTLEMENT_DELAY_SEC: DurationSec = 10 * 60;
+
+/// Balance of the underlying token compared to the amount owed by the contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Reconciliation {
+    #[serde(with = "u128_dec_format")]
+    pub underlying_balance: Balance,
+    /// Underlying tokens of holders, accrued penalties and transfers in flight
+    #[serde(with = "u128_dec_format")]
+    pub obligations: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub surplus: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub deficit: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Queries the balance of the underlying token and records it against the obligations.
+    /// Can be called by anyone.
+    pub fn reconcile(&mut self) -> Promise {
+        self.internal_query_underlying_balance()
+            .then(ext_self::on_reconcile(
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_ON_RECONCILE,
+            ))
+    }
+
+    /// Sends `amount` (the whole surplus by default) of underlying tokens which are not owed to
+    /// anyone to `receiver_id`.
+    ///
+    /// The sweep takes two calls. The balance observed by a query can include deposits which
+    /// the contract hasn't recorded yet, or refunds which the underlying token hasn't returned
+    /// yet. So the first call only records the surplus, and a call made at least
+    /// `SWEEP_SETTLEMENT_DELAY_SEC` later sweeps up to the smaller of the recorded and the fresh
+    /// surplus.
+    #[payable]
+    pub fn sweep_surplus(&mut self, receiver_id: ValidAccountId, amount: Option<U128>) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_query_underlying_balance()
+            .then(ext_self::on_sweep_balance(
+                receiver_id.into(),
+                amount,
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_ON_SWEEP,
+            ))
+    }
+
+    pub fn get_reconciliation(&self) -> Option<Reconciliation> {
+        self.last_reconciliation.clone()
+    }
+
+    #[private]
+    pub fn on_reconcile(&mut self, #[callback] underlying_balance: U128) -> Reconciliation {
+        self.internal_reconcile(underlying_balance.0)
+    }
+
+    pub fn get_sweep_reconciliation(&self) -> Option<Reconciliation> {
+        self.sweep_reconciliation.clone()
+    }
+
+    #[private]
+    pub fn on_sweep_balance(
+        &mut self,
+        #[callback] underlying_balance: U128,
+        receiver_id: AccountId,
+        amount: Option<U128>,
+    ) -> PromiseOrValue<bool> {
+        let reconciliation = self.internal_reconcile(underlying_balance.0);
+        let recorded = match self.sweep_reconciliation.take() {
+            Some(recorded) => recorded,
+            None => {
+                log!(
+                    "Recorded the surplus of {}, the sweep can be made in {} seconds",
+                    reconciliation.surplus,
+                    SWEEP_SETTLEMENT_DELAY_SEC
+                );
+                self.sweep_reconciliation = Some(reconciliation);
+                return PromiseOrValue::Value(false);
+            }
+        };
+        let settled_at = recorded
+            .timestamp
+            .saturating_add(u64::from(SWEEP_SETTLEMENT_DELAY_SEC) * 10u64.pow(9));
+        assert!(
+            reconciliation.timestamp >= settled_at,
+            "The sweep can be made {} seconds after the surplus was recorded",
+            SWEEP_SETTLEMENT_DELAY_SEC
+        );
+        let surplus = std::cmp::min(recorded.surplus, reconciliation.surplus);
+        let amount = amount.map(|a| a.0).unwrap_or(surplus);
+        assert!(amount > 0, "No surplus to sweep");
+        assert!(
+            amount <= surplus,
+            "Can't sweep more than the surplus of {}",
+            surplus
+        );
+        // The swept amount counts as owed until the transfer resolves
+        self.pending_transfers += amount;
+        Event::SurplusSweep {
+            receiver_id: receiver_id.clone(),
+            amount: U128(amount),
+        }
+        .emit();
+        ext_fungible_token::ft_transfer(
+            receiver_id,
+            U128(amount),
+            Some(format!("Surplus of {}", env::current_account_id())),
+            &self.locked_token_account_id,
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+        )
+        .then(ext_self::after_sweep_transfer(
+            U128(amount),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FT_TRANSFER,
+        ))
+        .into()
+    }
+}
+
+impl Contract {
+    fn internal_query_underlying_balance(&self) -> Promise {
+        ext_fungible_token::ft_balance_of(
+            env::current_account_id(),
+            &self.locked_token_account_id,
+            NO_DEPOSIT,
+            GAS_FOR_FT_BALANCE_OF,
+        )
+    }
+
+    pub(crate) fn internal_reconcile(&mut self, underlying_balance: Balance) -> Reconciliation {
+        let obligations = self.total_underlying + self.accrued_penalty + self.pending_transfers;
+        let reconciliation = Reconciliation {
+            underlying_balance,
+            obligations,
+            surplus: underlying_balance.saturating_sub(obligations),
+            deficit: obligations.saturating_sub(underlying_balance),
+            timestamp: env::block_timestamp(),
+        };
+        if reconciliation.deficit > 0 {
+            log!(
+                "Underlying balance {} is below the obligations {}",
+                underlying_balance,
+                obligations
+            );
+        }
+        self.last_reconciliation = Some(reconciliation.clone());
+        reconciliation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::SEC;
+    use crate::tests::{
+        set_promise_result, set_promise_result_at, set_yocto_context, setup_unlocked_contract,
+    };
+    use near_sdk::test_utils::accounts;
+
+    fn balance_result(balance: Balance) -> PromiseResult {
+        PromiseResult::Successful(serde_json::to_vec(&U128(balance)).unwrap())
+    }
+
+    #[test]
+    pub fn test_reconcile() {
+        let mut contract = setup_unlocked_contract(100);
+        contract.accrued_penalty = 10;
+        set_yocto_context(2, 0);
+        contract.unwrap(Some(U128(40)), None);
+
+        // The unwrapped tokens are still owed while the transfer is in flight
+        let reconciliation = contract.internal_reconcile(135);
+        assert_eq!(reconciliation.obligations, 110);
+        assert_eq!(reconciliation.surplus, 25);
+        assert_eq!(reconciliation.deficit, 0);
+
+        set_promise_result(PromiseResult::Successful(vec![]));
+        contract.after_ft_transfer(accounts(2).into(), U128(40));
+        let reconciliation = contract.internal_reconcile(60);
+        assert_eq!(reconciliation.obligations, 70);
+        assert_eq!(reconciliation.surplus, 0);
+        assert_eq!(reconciliation.deficit, 10);
+        assert_eq!(contract.get_reconciliation().unwrap().deficit, 10);
+    }
+
+    #[test]
+    pub fn test_sweep_surplus() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(5, 1000 * SEC);
+        contract.sweep_surplus(accounts(4), None);
+
+        // The first step only records the surplus
+        set_promise_result_at(balance_result(130), 1000 * SEC);
+        assert!(matches!(
+            contract.on_sweep_balance(U128(130), accounts(4).into(), None),
+            PromiseOrValue::Value(false)
+        ));
+        assert_eq!(contract.get_sweep_reconciliation().unwrap().surplus, 30);
+        assert_eq!(contract.pending_transfers, 0);
+
+        set_promise_result_at(balance_result(140), 1600 * SEC);
+        assert!(matches!(
+            contract.on_sweep_balance(U128(140), accounts(4).into(), None),
+            PromiseOrValue::Promise(_)
+        ));
+        assert!(contract.get_sweep_reconciliation().is_none());
+        assert_eq!(contract.pending_transfers, 30);
+        assert_eq!(contract.internal_reconcile(140).surplus, 10);
+
+        set_promise_result(PromiseResult::Successful(vec![]));
+        assert!(contract.after_sweep_transfer(U128(30)));
+        assert_eq!(contract.pending_transfers, 0);
+        assert_eq!(contract.internal_reconcile(110).surplus, 10);
+    }
+
+    #[test]
+    pub fn test_failed_sweep_transfer() {
+        let mut contract = setup_unlocked_contract(100);
+        set_promise_result_at(balance_result(130), 1000 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), None);
+        set_promise_result_at(balance_result(130), 1600 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), Some(U128(20)));
+        assert_eq!(contract.pending_transfers, 20);
+
+        set_promise_result(PromiseResult::Failed);
+        assert!(!contract.after_sweep_transfer(U128(20)));
+        assert_eq!(contract.pending_transfers, 0);
+        assert_eq!(contract.internal_reconcile(130).surplus, 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't sweep more than the surplus of 30")]
+    pub fn test_sweep_in_flight_deposit() {
+        let mut contract = setup_unlocked_contract(100);
+        set_promise_result_at(balance_result(130), 1000 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), None);
+
+        // The balance includes a deposit of 50 which the contract hasn't recorded yet
+        set_promise_result_at(balance_result(180), 1600 * SEC);
+        contract.on_sweep_balance(U128(180), accounts(4).into(), Some(U128(80)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The sweep can be made 600 seconds after the surplus was recorded")]
+    pub fn test_sweep_too_early() {
+        let mut contract = setup_unlocked_contract(100);
+        set_promise_result_at(balance_result(130), 1000 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), None);
+        set_promise_result_at(balance_result(130), 1599 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No surplus to sweep")]
+    pub fn test_sweep_without_surplus() {
+        let mut contract = setup_unlocked_contract(100);
+        set_promise_result_at(balance_result(130), 1000 * SEC);
+        contract.on_sweep_balance(U128(130), accounts(4).into(), None);
+        set_promise_result_at(balance_result(100), 1600 * SEC);
+        contract.on_sweep_balance(U128(100), accounts(4).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No Access")]
+    pub fn test_sweep_by_other_account() {
+        let mut contract = setup_unlocked_contract(100);
+        set_yocto_context(2, 1000 * SEC);
+        contract.sweep_surplus(accounts(2), None);
+    }
+}
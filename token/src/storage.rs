@@ -0,0 +1,157 @@
+use crate::*;
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds};
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<ValidAccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        self.ft.storage_deposit(account_id, registration_only)
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.ft.storage_withdraw(amount)
+    }
+
+    /// The storage deposit of accounts registered from the storage pool goes back to the pool.
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            !self.pending_unwraps.contains_key(&account_id),
+            "Can't unregister the account while its unwraps are in flight"
+        );
+        if self.sponsored_accounts.contains(&account_id) {
+            assert_one_yocto();
+            let balance = self.ft.accounts.get(&account_id).unwrap_or(0);
+            assert!(
+                balance == 0 || force.unwrap_or(false),
+                "Can't unregister the account with the positive balance without force"
+            );
+            self.ft.accounts.remove(&account_id);
+            self.ft.total_supply -= balance;
+            self.on_account_closed(account_id, balance);
+            true
+        } else if let Some((account_id, balance)) = self.ft.internal_storage_unregister(force) {
+            self.on_account_closed(account_id, balance);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.ft.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: ValidAccountId) -> Option<StorageBalance> {
+        self.ft.storage_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the pool which pays the storage of new depositors.
+    #[payable]
+    pub fn fund_storage_pool(&mut self) -> U128 {
+        self.storage_pool += env::attached_deposit();
+        U128(self.storage_pool)
+    }
+
+    pub fn get_storage_pool(&self) -> U128 {
+        U128(self.storage_pool)
+    }
+
+    pub fn is_storage_sponsored(&self, account_id: ValidAccountId) -> bool {
+        self.sponsored_accounts.contains(account_id.as_ref())
+    }
+}
+
+impl Contract {
+    /// Registers the account paying its storage from the pool. Returns false if the pool is
+    /// too small.
+    pub(crate) fn internal_register_sponsored(&mut self, account_id: &AccountId) -> bool {
+        let storage_cost = self.storage_balance_bounds().min.0;
+        if self.storage_pool < storage_cost {
+            return false;
+        }
+        self.storage_pool -= storage_cost;
+        self.ft.internal_register_account(account_id);
+        self.sponsored_accounts.insert(account_id);
+        true
+    }
+
+    /// Releases the storage of a closed account. Returns true if it was paid from the pool,
+    /// in which case the storage deposit goes back to the pool.
+    pub(crate) fn internal_release_sponsored(&mut self, account_id: &AccountId) -> bool {
+        if self.sponsored_accounts.remove(account_id) {
+            self.storage_pool += self.storage_balance_bounds().min.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_receiver::tests::{set_context, setup_contract};
+    use crate::tests::set_yocto_context;
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    pub fn test_sponsored_registration() {
+        let mut contract = setup_contract(1);
+        let storage_cost = contract.storage_balance_bounds().min.0;
+        contract.storage_pool = storage_cost;
+        set_context(1, 0);
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), String::new()),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert!(contract.is_storage_sponsored(accounts(2)));
+        assert_eq!(contract.get_storage_pool().0, 0);
+
+        // The pool is empty
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(3), U128(100), String::new()),
+            PromiseOrValue::Value(U128(100))
+        ));
+
+        set_yocto_context(2, 0);
+        assert!(contract.storage_unregister(Some(true)));
+        assert!(!contract.is_storage_sponsored(accounts(2)));
+        assert_eq!(contract.get_storage_pool().0, storage_cost);
+    }
+
+    #[test]
+    pub fn test_small_deposits_are_not_sponsored() {
+        let mut contract = setup_contract(1);
+        let storage_cost = contract.storage_balance_bounds().min.0;
+        contract.storage_pool = storage_cost;
+        set_context(1, 0);
+        let msg = format!(r#"{{"receiver_id": "{}"}}"#, accounts(4));
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(99), msg.clone()),
+            PromiseOrValue::Value(U128(99))
+        ));
+        assert!(!contract.is_storage_sponsored(accounts(4)));
+        assert_eq!(contract.get_storage_pool().0, storage_cost);
+
+        // Registered accounts can deposit any amount
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), msg.clone()),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(1), msg),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 101);
+    }
+}